@@ -0,0 +1,16 @@
+-- Run once before `cargo make migration` on databases that still have users.age:
+-- psqldef would drop the column without carrying the values over.
+-- Only the birth year can be recovered, so everyone is assumed to be born on January 1st.
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT FROM information_schema.columns
+        WHERE table_name = 'users' AND column_name = 'age'
+    ) THEN
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS birth_date DATE;
+        UPDATE users
+        SET birth_date = make_date(EXTRACT(YEAR FROM current_date)::int - age, 1, 1)
+        WHERE age IS NOT NULL AND birth_date IS NULL;
+    END IF;
+END
+$$;
@@ -1,5 +1,81 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
 CREATE TABLE users (
-  id   BIGSERIAL    NOT NULL PRIMARY KEY,
-  name VARCHAR(255) NOT NULL,
-  age  INTEGER
+  id         BIGSERIAL    NOT NULL PRIMARY KEY,
+  name       VARCHAR(255) NOT NULL,
+  email      VARCHAR(320),
+  -- A backstop only: the application keeps ages within 0 to 150 years as of today.
+  birth_date DATE         CONSTRAINT users_birth_date_check CHECK (birth_date >= DATE '1850-01-01'),
+  deleted_at TIMESTAMP WITH TIME ZONE,
+  version    BIGINT       NOT NULL DEFAULT 1
 );
+
+CREATE INDEX users_name_trgm_idx ON users USING GIN (name gin_trgm_ops);
+
+-- Addresses are stored case-folded, so this also rejects ones differing only in case.
+CREATE UNIQUE INDEX users_email_key ON users (email);
+
+CREATE TABLE user_credentials (
+  user_id       BIGINT       NOT NULL PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+  -- PHC string, which carries the algorithm and parameters it was made with.
+  password_hash VARCHAR(255) NOT NULL,
+  updated_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+-- Only the SHA-256 of a token is kept.
+CREATE TABLE user_sessions (
+  token_hash VARCHAR(64) NOT NULL PRIMARY KEY,
+  user_id    BIGINT      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX user_sessions_user_id_idx ON user_sessions (user_id);
+
+-- Users without a role can only read and edit themselves.
+CREATE TABLE user_roles (
+  user_id    BIGINT      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  role       VARCHAR(16) NOT NULL CONSTRAINT user_roles_role_check CHECK (role IN ('admin', 'operator', 'viewer')),
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  PRIMARY KEY (user_id, role)
+);
+
+-- Keys for machine clients. Only the SHA-256 of a key is kept, along with its first characters so
+-- people can tell keys apart.
+CREATE TABLE api_keys (
+  id           BIGSERIAL    NOT NULL PRIMARY KEY,
+  prefix       VARCHAR(16)  NOT NULL,
+  key_hash     VARCHAR(64)  NOT NULL,
+  owner        VARCHAR(255) NOT NULL,
+  -- Space separated, as in OAuth 2.0.
+  scopes       VARCHAR(255) NOT NULL,
+  expires_at   TIMESTAMP WITH TIME ZONE,
+  last_used_at TIMESTAMP WITH TIME ZONE,
+  revoked_at   TIMESTAMP WITH TIME ZONE,
+  created_at   TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX api_keys_key_hash_key ON api_keys (key_hash);
+
+-- No foreign key to users: the log has to outlive purged users.
+CREATE TABLE user_audit_log (
+  id         BIGSERIAL    NOT NULL PRIMARY KEY,
+  user_id    BIGINT       NOT NULL,
+  actor      VARCHAR(255) NOT NULL,
+  operation  VARCHAR(16)  NOT NULL,
+  diff       JSONB        NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX user_audit_log_user_id_idx ON user_audit_log (user_id, id);
+
+CREATE TABLE outbox (
+  id           BIGSERIAL   NOT NULL PRIMARY KEY,
+  event_type   VARCHAR(64) NOT NULL,
+  payload      JSONB       NOT NULL,
+  created_at   TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+  published_at TIMESTAMP WITH TIME ZONE,
+  failed_at    TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX outbox_unpublished_idx ON outbox (id) WHERE published_at IS NULL AND failed_at IS NULL;
@@ -1,5 +1,20 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::{Args, Parser, Subcommand};
-use rust_app_example::cli::user::create_user;
+use rust_app_example::{
+    cli::{
+        api_key::{create_api_key, list_api_keys, revoke_api_key, rotate_api_key},
+        audit::get_user_history,
+        user::{create_user, export_users, grant_role, import_users, list_roles, revoke_role},
+    },
+    domain::{
+        api_key::{ApiKey, ApiKeySecret, Scope},
+        role::Role,
+    },
+    infrastructure::import::Format,
+    interface::export::ExportFormat,
+};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -10,16 +25,123 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// create user
+    /// create user and print it as JSON
     CreateUser(CreateUser),
+    /// import users from a file, all or nothing
+    ImportUsers(ImportUsers),
+    /// write all users to standard output
+    ExportUsers(ExportUsers),
+    /// show the audit log of a user
+    Audit(Audit),
+    /// manage API keys for machine clients
+    #[clap(subcommand)]
+    ApiKey(ApiKeyCommands),
+    /// manage the roles of users
+    #[clap(subcommand)]
+    Role(RoleCommands),
 }
 
 #[derive(Args)]
 struct CreateUser {
     #[clap(value_parser)]
     name: String,
+    /// as YYYY-MM-DD
+    #[clap(value_parser)]
+    birth_date: Option<NaiveDate>,
+    #[clap(long, value_parser)]
+    email: Option<String>,
+}
+
+#[derive(Args)]
+struct ImportUsers {
+    /// csv or ndjson
+    #[clap(long, value_parser)]
+    format: Format,
+    /// validate the file without importing it
+    #[clap(long)]
+    dry_run: bool,
+    #[clap(value_parser)]
+    file: PathBuf,
+}
+
+#[derive(Args)]
+struct ExportUsers {
+    /// csv, ndjson or json
+    #[clap(long, value_parser)]
+    format: ExportFormat,
+}
+
+#[derive(Args)]
+struct Audit {
+    /// public id of the user, as in the API
+    #[clap(value_parser)]
+    id: String,
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommands {
+    /// create a key and print it, which is the only time it is shown
+    Create(CreateApiKey),
+    /// list keys including revoked ones
+    List,
+    /// revoke a key
+    Revoke(ApiKeyId),
+    /// replace a key with a new one with the same owner, scopes and expiry
+    Rotate(ApiKeyId),
+}
+
+#[derive(Args)]
+struct CreateApiKey {
+    /// who is responsible for the key
+    #[clap(long, value_parser)]
+    owner: String,
+    /// users:read or users:write, repeatable
+    #[clap(long = "scope", value_parser, required = true)]
+    scopes: Vec<Scope>,
+    /// as RFC 3339, such as 2024-01-01T00:00:00Z
+    #[clap(long, value_parser)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Args)]
+struct ApiKeyId {
     #[clap(value_parser)]
-    age: u32,
+    id: i64,
+}
+
+#[derive(Subcommand)]
+enum RoleCommands {
+    /// list the roles of a user
+    List(UserRoles),
+    /// grant a user a role
+    Grant(UserRole),
+    /// take a role from a user
+    Revoke(UserRole),
+}
+
+#[derive(Args)]
+struct UserRoles {
+    /// public id of the user, as in the API
+    #[clap(value_parser)]
+    id: String,
+}
+
+#[derive(Args)]
+struct UserRole {
+    /// public id of the user, as in the API
+    #[clap(value_parser)]
+    id: String,
+    /// admin, operator or viewer
+    #[clap(value_parser)]
+    role: Role,
+}
+
+fn print_secret(key: &ApiKey, secret: &ApiKeySecret) {
+    eprintln!(
+        "API key {} created, store it now as it is not shown again:",
+        key.id.0
+    );
+    println!("{}", secret.0);
 }
 
 #[tokio::main]
@@ -28,8 +150,83 @@ async fn main() -> anyhow::Result<()> {
 
     match cli.command {
         Commands::CreateUser(args) => {
-            let user = create_user(args.name, args.age).await?;
-            dbg!(user);
+            let user = create_user(args.name, args.email, args.birth_date).await?;
+            println!("{}", serde_json::to_string(&user)?);
+        }
+        Commands::ImportUsers(args) => {
+            let report = import_users(&args.file, args.format, args.dry_run).await?;
+            for error in &report.errors {
+                eprintln!("{}:{}: {}", args.file.display(), error.line, error.message);
+            }
+            println!(
+                "{} rows, {} imported{}",
+                report.rows,
+                report.imported,
+                if report.dry_run { " (dry run)" } else { "" }
+            );
+            if !report.is_ok() {
+                std::process::exit(1);
+            }
+        }
+        Commands::ExportUsers(args) => {
+            export_users(args.format, tokio::io::stdout()).await?;
+        }
+        Commands::Audit(args) => {
+            for entry in get_user_history(&args.id).await? {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    entry.id,
+                    entry.created_at.to_rfc3339(),
+                    entry.actor.0,
+                    entry.operation.as_str(),
+                    entry.diff,
+                );
+            }
+        }
+        Commands::ApiKey(ApiKeyCommands::Create(args)) => {
+            let (key, secret) = create_api_key(args.owner, args.scopes, args.expires_at).await?;
+            print_secret(&key, &secret);
+        }
+        Commands::ApiKey(ApiKeyCommands::List) => {
+            for key in list_api_keys().await? {
+                println!(
+                    "{}\t{}\t{}\t{}\texpires {}\tlast used {}{}",
+                    key.id.0,
+                    key.prefix,
+                    key.owner,
+                    key.scopes
+                        .iter()
+                        .map(Scope::as_str)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    key.expires_at.map_or("never".into(), |x| x.to_rfc3339()),
+                    key.last_used_at.map_or("never".into(), |x| x.to_rfc3339()),
+                    key.revoked_at
+                        .map_or(String::new(), |x| format!("\trevoked {}", x.to_rfc3339())),
+                );
+            }
+        }
+        Commands::ApiKey(ApiKeyCommands::Revoke(args)) => {
+            let key = revoke_api_key(args.id).await?;
+            eprintln!("API key {} ({}) revoked", key.id.0, key.prefix);
+        }
+        Commands::ApiKey(ApiKeyCommands::Rotate(args)) => {
+            let (key, secret) = rotate_api_key(args.id).await?;
+            eprintln!("API key {} revoked", args.id);
+            print_secret(&key, &secret);
+        }
+        Commands::Role(RoleCommands::List(args)) => {
+            for role in list_roles(&args.id).await? {
+                println!("{}", role);
+            }
+        }
+        Commands::Role(RoleCommands::Grant(args)) => {
+            grant_role(&args.id, args.role).await?;
+            eprintln!("user {} is now {}", args.id, args.role);
+        }
+        Commands::Role(RoleCommands::Revoke(args)) => {
+            revoke_role(&args.id, args.role).await?;
+            eprintln!("user {} is no longer {}", args.id, args.role);
         }
     }
 
@@ -1 +1,14 @@
+use anyhow::anyhow;
+
+use crate::{config::CONFIG, domain::user::UserId, interface::public_id::PublicIds};
+
+pub mod api_key;
+pub mod audit;
 pub mod user;
+
+/// The user named by a public id, so that the CLI takes the same ids as the API.
+fn user_id(public_id: &str) -> anyhow::Result<UserId> {
+    PublicIds::new(&CONFIG.public_id_secret)
+        .decode(public_id)
+        .ok_or_else(|| anyhow!("{} is not a user id", public_id))
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    domain::api_key::{ApiKey, ApiKeyId, ApiKeySecret, NewApiKey, Scope},
+    infrastructure::{
+        repository::rdb::{create_connection, RdbRepository},
+        token::RandomTokens,
+    },
+    usecase::api_key::{create::CreateApiKey, list::ListApiKeys, revoke::RevokeApiKey},
+};
+
+pub async fn create_api_key(
+    owner: String,
+    scopes: Vec<Scope>,
+    expires_at: Option<DateTime<Utc>>,
+) -> anyhow::Result<(ApiKey, ApiKeySecret)> {
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(CreateApiKey::new(&repo, &RandomTokens)
+        .run(NewApiKey {
+            owner,
+            scopes,
+            expires_at,
+        })
+        .await?)
+}
+
+pub async fn list_api_keys() -> anyhow::Result<Vec<ApiKey>> {
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(ListApiKeys::new(&repo).run().await?)
+}
+
+pub async fn revoke_api_key(id: i64) -> anyhow::Result<ApiKey> {
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(RevokeApiKey::new(&repo).run(&ApiKeyId(id)).await?)
+}
+
+pub async fn rotate_api_key(id: i64) -> anyhow::Result<(ApiKey, ApiKeySecret)> {
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(CreateApiKey::new(&repo, &RandomTokens)
+        .rotate(&ApiKeyId(id))
+        .await?)
+}
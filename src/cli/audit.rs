@@ -0,0 +1,19 @@
+use crate::{
+    domain::audit::AuditEntry,
+    infrastructure::repository::rdb::{create_connection, RdbRepository},
+    usecase::{
+        policy::{Policy, Subject},
+        user::history::GetUserHistory,
+    },
+};
+
+use super::user_id;
+
+pub async fn get_user_history(id: &str) -> anyhow::Result<Vec<AuditEntry>> {
+    let id = user_id(id)?;
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(GetUserHistory::new(&repo, &Policy)
+        .run(&Subject::System, &id)
+        .await?)
+}
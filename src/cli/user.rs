@@ -1,15 +1,126 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use futures::StreamExt;
+use sea_orm::TransactionTrait;
+use tokio::{
+    fs::File,
+    io::{AsyncWrite, AsyncWriteExt},
+};
+
 use crate::{
-    domain::user::{NewUser, User},
-    infrastructure::repository::memory::OnMemoryRepository,
-    usecase::user::create::CreateUser,
+    config::CONFIG,
+    domain::{
+        clock::Clock,
+        role::Role,
+        user::{import::ImportReport, query::UserQuery, NewUser},
+    },
+    infrastructure::{
+        clock::SystemClock,
+        import::{stream_users, Format},
+        repository::{
+            memory::OnMemoryRepository,
+            rdb::{create_connection, RdbRepository},
+        },
+    },
+    interface::{
+        export::{encode, ExportFormat},
+        presenter::UserView,
+        public_id::PublicIds,
+    },
+    usecase::{
+        policy::{Policy, Subject},
+        user::{create::CreateUser, export::ExportUsers, import::ImportUsers, roles::ManageRoles},
+    },
 };
 
-pub async fn create_user(name: String, age: u32) -> anyhow::Result<User> {
+use super::user_id;
+
+pub async fn create_user(
+    name: String,
+    email: Option<String>,
+    birth_date: Option<NaiveDate>,
+) -> anyhow::Result<UserView> {
     let repo = OnMemoryRepository::new();
-    CreateUser::new(&repo)
-        .run(NewUser {
-            name: name,
-            age: age,
-        })
-        .await
+    let user = CreateUser::new(&repo, &Policy, &SystemClock)
+        .run(
+            &Subject::System,
+            NewUser {
+                name,
+                email,
+                birth_date,
+            },
+        )
+        .await?;
+    Ok(UserView::new(
+        user,
+        &PublicIds::new(&CONFIG.public_id_secret),
+        SystemClock.today(),
+    ))
+}
+
+pub async fn import_users(
+    path: &Path,
+    format: Format,
+    dry_run: bool,
+) -> anyhow::Result<ImportReport> {
+    let file = File::open(path).await?;
+    let conn = create_connection().await?;
+    let tx = conn.begin().await?;
+    let report = ImportUsers::new(&RdbRepository::new(&tx), &Policy, &SystemClock)
+        .run(&Subject::System, stream_users(format, file), dry_run)
+        .await?;
+    if report.is_ok() {
+        tx.commit().await?;
+    }
+    Ok(report)
+}
+
+pub async fn export_users(
+    format: ExportFormat,
+    mut out: impl AsyncWrite + Unpin,
+) -> anyhow::Result<()> {
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    let users = ExportUsers::new(&repo, &Policy)
+        .run(&Subject::System, &UserQuery::default())
+        .await?;
+    let mut chunks = encode(
+        format,
+        users,
+        PublicIds::new(&CONFIG.public_id_secret),
+        SystemClock.today(),
+    );
+    while let Some(chunk) = chunks.next().await {
+        out.write_all(&chunk?).await?;
+    }
+    out.flush().await?;
+    Ok(())
+}
+
+pub async fn list_roles(id: &str) -> anyhow::Result<Vec<Role>> {
+    let id = user_id(id)?;
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(ManageRoles::new(&repo, &Policy)
+        .list(&Subject::System, &id)
+        .await?)
+}
+
+pub async fn grant_role(id: &str, role: Role) -> anyhow::Result<()> {
+    let id = user_id(id)?;
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(ManageRoles::new(&repo, &Policy)
+        .grant(&Subject::System, &id, role)
+        .await?)
+}
+
+pub async fn revoke_role(id: &str, role: Role) -> anyhow::Result<()> {
+    let id = user_id(id)?;
+    let conn = create_connection().await?;
+    let repo = RdbRepository::new(&conn);
+    Ok(ManageRoles::new(&repo, &Policy)
+        .revoke(&Subject::System, &id, role)
+        .await?)
 }
@@ -1,5 +1,11 @@
+use std::{env, fmt::Debug, path::PathBuf, str::FromStr};
+
+use chrono::Duration;
+use jsonwebtoken::Algorithm;
 use once_cell::sync::Lazy;
 
+use crate::domain::credential::PasswordPolicy;
+
 pub static CONFIG: Lazy<Config> = Lazy::new(|| Config {
     postgres_host: std::env::var("POSTGRES_HOST").unwrap(),
     postgres_port: std::env::var("POSTGRES_PORT").unwrap(),
@@ -9,14 +15,68 @@ pub static CONFIG: Lazy<Config> = Lazy::new(|| Config {
     postgres_database: std::env::var("POSTGRES_DB").unwrap(),
     #[cfg(test)]
     postgres_database: std::env::var("POSTGRES_DB_TEST").unwrap(),
+    #[cfg(not(test))]
+    public_id_secret: std::env::var("PUBLIC_ID_SECRET").unwrap(),
+    #[cfg(test)]
+    public_id_secret: "test".into(),
+    password_policy: PasswordPolicy {
+        min_length: env_or("PASSWORD_MIN_LENGTH", 12),
+        require_lowercase: env_or("PASSWORD_REQUIRE_LOWERCASE", false),
+        require_uppercase: env_or("PASSWORD_REQUIRE_UPPERCASE", false),
+        require_digit: env_or("PASSWORD_REQUIRE_DIGIT", false),
+        require_symbol: env_or("PASSWORD_REQUIRE_SYMBOL", false),
+    },
+    #[cfg(not(test))]
+    argon2_params: argon2::Params::new(
+        env_or("ARGON2_M_COST", argon2::Params::DEFAULT_M_COST),
+        env_or("ARGON2_T_COST", argon2::Params::DEFAULT_T_COST),
+        env_or("ARGON2_P_COST", argon2::Params::DEFAULT_P_COST),
+        None,
+    )
+    .unwrap(),
+    // The smallest cost there is, as the tests hash a lot.
+    #[cfg(test)]
+    argon2_params: argon2::Params::new(argon2::Params::MIN_M_COST, 1, 1, None).unwrap(),
+    session_ttl: Duration::seconds(env_or("SESSION_TTL_SECONDS", 24 * 60 * 60)),
+    jwt_algorithm: env_or("JWT_ALGORITHM", Algorithm::HS256),
+    #[cfg(not(test))]
+    jwt_key_file: std::env::var("JWT_KEY_FILE").unwrap().into(),
+    #[cfg(test)]
+    jwt_key_file: concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/jwt/hs256.key").into(),
+    jwt_issuer: env::var("JWT_ISSUER").ok(),
+    jwt_audience: env::var("JWT_AUDIENCE").ok(),
 });
 
+/// The variable parsed, or `default` if it is not set.
+fn env_or<T: FromStr>(key: &str, default: T) -> T
+where
+    T::Err: Debug,
+{
+    env::var(key).map_or(default, |x| x.parse().unwrap())
+}
+
+#[derive(Clone)]
 pub struct Config {
     postgres_host: String,
     postgres_port: String,
     postgres_user: String,
     postgres_password: String,
     postgres_database: String,
+    /// Key of the encoding of user ids in the API. Changing it changes every public id.
+    pub public_id_secret: String,
+    pub password_policy: PasswordPolicy,
+    /// Cost of new password hashes. Stored hashes made with other costs are redone at sign in.
+    pub argon2_params: argon2::Params,
+    /// How long a session token stays valid.
+    pub session_ttl: Duration,
+    /// Either `HS256` or `RS256`.
+    pub jwt_algorithm: Algorithm,
+    /// The shared secret for HS256, or the PEM encoded public key for RS256.
+    pub jwt_key_file: PathBuf,
+    /// Required `iss` of bearer tokens, if any.
+    pub jwt_issuer: Option<String>,
+    /// Required `aud` of bearer tokens, if any.
+    pub jwt_audience: Option<String>,
 }
 
 impl Config {
@@ -1,2 +1,11 @@
+pub mod api_key;
+pub mod audit;
+pub mod clock;
+pub mod credential;
+pub mod error;
+pub mod event;
+pub mod id;
+pub mod pagination;
 pub mod repository;
+pub mod role;
 pub mod user;
@@ -0,0 +1,192 @@
+use std::{
+    fmt::{self, Debug, Display},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::credential::TokenGenerator;
+
+/// What an API key may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Scope {
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 2] = [Scope::UsersRead, Scope::UsersWrite];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+        }
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|x| x.as_str() == s)
+            .ok_or_else(|| format!("unknown scope: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ApiKeyId(pub i64);
+
+/// A key for machine clients. The secret itself is only known when the key is made.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    /// The start of the secret, to tell keys apart without revealing them.
+    pub prefix: String,
+    /// Who is responsible for the key, such as a batch job or team.
+    pub owner: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// Whether the key can be used at `now`: neither revoked nor expired.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map_or(true, |x| now < x)
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Validate)]
+pub struct NewApiKey {
+    #[validate(length(min = 1, max = 255))]
+    pub owner: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// The secret of an API key, as clients send it. Only its hash is stored.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKeySecret(pub String);
+
+impl ApiKeySecret {
+    /// Marks a bearer token as an API key rather than a JWT.
+    pub const MARKER: &'static str = "exk_";
+    const PREFIX_LEN: usize = Self::MARKER.len() + 8;
+
+    pub fn generate(tokens: &dyn TokenGenerator) -> Self {
+        Self(format!("{}{}", Self::MARKER, tokens.random_token()))
+    }
+
+    /// Whether `token` looks like an API key, which does not make it a valid one.
+    pub fn is_api_key(token: &str) -> bool {
+        token.starts_with(Self::MARKER)
+    }
+
+    pub fn prefix(&self) -> &str {
+        self.0.get(..Self::PREFIX_LEN).unwrap_or(&self.0)
+    }
+
+    pub fn hash(&self, tokens: &dyn TokenGenerator) -> String {
+        tokens.hash_token(&self.0)
+    }
+}
+
+impl Debug for ApiKeySecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ApiKeySecret")
+            .field(&format!("{}...", self.prefix()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::credential::MockTokenGenerator;
+
+    use super::*;
+
+    fn key(expires_in: Option<Duration>, revoked: bool) -> ApiKey {
+        let now = Utc::now();
+        ApiKey {
+            id: ApiKeyId(1),
+            prefix: "exk_abcdefgh".into(),
+            owner: "batch".into(),
+            scopes: vec![Scope::UsersRead],
+            expires_at: expires_in.map(|x| now + x),
+            last_used_at: None,
+            revoked_at: revoked.then_some(now),
+            created_at: now,
+        }
+    }
+
+    #[rstest]
+    #[case(None, false, true)]
+    #[case(Some(Duration::hours(1)), false, true)]
+    #[case(Some(Duration::hours(-1)), false, false)]
+    #[case(None, true, false)]
+    fn test_is_active(
+        #[case] expires_in: Option<Duration>,
+        #[case] revoked: bool,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(key(expires_in, revoked).is_active(Utc::now()), expected);
+    }
+
+    #[test]
+    fn test_scope() {
+        for scope in Scope::ALL {
+            assert_eq!(scope.as_str().parse(), Ok(scope));
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::json!(scope.as_str())
+            );
+        }
+        assert!("users:delete".parse::<Scope>().is_err());
+        assert!(key(None, false).has_scope(Scope::UsersRead));
+        assert!(!key(None, false).has_scope(Scope::UsersWrite));
+    }
+
+    #[test]
+    fn test_secret() {
+        let mut tokens = MockTokenGenerator::new();
+        tokens
+            .expect_random_token()
+            .returning(|| "abcdefghijklmnop".into());
+        tokens
+            .expect_hash_token()
+            .returning(|x| format!("hash of {}", x));
+
+        let secret = ApiKeySecret::generate(&tokens);
+
+        assert_eq!(secret.0, "exk_abcdefghijklmnop");
+        assert!(ApiKeySecret::is_api_key(&secret.0));
+        assert_eq!(secret.prefix(), "exk_abcdefgh");
+        assert_eq!(secret.hash(&tokens), "hash of exk_abcdefghijklmnop");
+        assert!(!format!("{:?}", secret).contains(&secret.0));
+        assert!(!ApiKeySecret::is_api_key("eyJhbGciOiJIUzI1NiJ9"));
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::user::UserId;
+
+/// Who performed a change.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Actor(pub String);
+
+impl Actor {
+    /// Changes made by the application itself rather than on behalf of someone.
+    pub fn system() -> Self {
+        Self("system".into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Create => "create",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+            Operation::Restore => "restore",
+            Operation::Purge => "purge",
+        }
+    }
+}
+
+/// A recorded change to a user.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub user_id: UserId,
+    pub actor: Actor,
+    pub operation: Operation,
+    /// `{"before": .., "after": ..}` holding only the fields that changed. `before` is `null` on
+    /// create and `after` is `null` on purge.
+    pub diff: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,26 @@
+use std::fmt::Debug;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Source of the current time, so that ages and timestamps can be fixed in tests.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+/// A clock that is always at midnight UTC on the same day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub NaiveDate);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_utc(self.0.and_hms_opt(0, 0, 0).unwrap(), Utc)
+    }
+
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt::{self, Debug};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use super::{error::Result, user::UserId};
+
+/// Rules a new password has to follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    /// Requires a character that is neither a letter nor a digit.
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 12,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
+
+/// Hashing is slow on purpose, so no password is accepted that would make it slower still.
+pub const MAX_PASSWORD_LENGTH: u64 = 1024;
+
+/// A password a user wants to set, validated with `validate_args(&policy)`.
+#[derive(Clone, Deserialize, Validate)]
+pub struct NewPassword {
+    #[validate(
+        length(max = "MAX_PASSWORD_LENGTH"),
+        custom(function = "validate_password", arg = "&'v_a PasswordPolicy")
+    )]
+    pub password: String,
+}
+
+impl Debug for NewPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NewPassword").finish_non_exhaustive()
+    }
+}
+
+/// Fails with a code naming the first rule of `policy` the password breaks.
+fn validate_password(password: &str, policy: &PasswordPolicy) -> Result<(), ValidationError> {
+    let has = |f: fn(char) -> bool| password.chars().any(f);
+    let broken = if password.chars().count() < policy.min_length {
+        let mut e = ValidationError::new("too_short");
+        e.add_param("min".into(), &policy.min_length);
+        return Err(e);
+    } else if policy.require_lowercase && !has(char::is_lowercase) {
+        "missing_lowercase"
+    } else if policy.require_uppercase && !has(char::is_uppercase) {
+        "missing_uppercase"
+    } else if policy.require_digit && !has(|x| x.is_ascii_digit()) {
+        "missing_digit"
+    } else if policy.require_symbol && !has(|x| !x.is_alphanumeric()) {
+        "missing_symbol"
+    } else {
+        return Ok(());
+    };
+    Err(ValidationError::new(broken))
+}
+
+/// An email address and password to sign in with.
+#[derive(Clone, Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("email", &self.email)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A password hash in the PHC string format, which records the algorithm and its parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordHash(pub String);
+
+/// The stored password of a user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credential {
+    pub user_id: UserId,
+    pub password_hash: PasswordHash,
+}
+
+/// Hashing and verifying are slow on purpose, so implementations keep them off the async runtime.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait PasswordHasher: Debug + Send + Sync {
+    async fn hash(&self, password: &str) -> Result<PasswordHash>;
+    /// Whether `password` is the one `hash` was made from, compared in constant time.
+    async fn verify(&self, password: &str, hash: &PasswordHash) -> Result<bool>;
+    /// Whether `hash` was made with other parameters than new hashes are.
+    fn needs_rehash(&self, hash: &PasswordHash) -> bool;
+}
+
+/// What replacing a password does to the user's sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sessions {
+    /// For the same password hashed anew.
+    Keep,
+    /// For a new password, so that whoever knew the old one is signed out.
+    Revoke,
+}
+
+/// Source of the random secrets behind session tokens and API keys, and of the hashes they are
+/// stored under.
+#[cfg_attr(test, mockall::automock)]
+pub trait TokenGenerator: Debug + Send + Sync {
+    /// A secret too random to guess, made of characters that are safe in a header.
+    fn random_token(&self) -> String;
+    /// What a token is stored and looked up by. Tokens are random enough not to need a slow hash.
+    fn hash_token(&self, token: &str) -> String;
+}
+
+/// A bearer token for a signed in user. Only its hash is stored, so a leaked table does not leak
+/// sessions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionToken(pub String);
+
+impl SessionToken {
+    /// Marks a bearer token as a session rather than a JWT or an API key.
+    pub const MARKER: &'static str = "exs_";
+
+    pub fn generate(tokens: &dyn TokenGenerator) -> Self {
+        Self(format!("{}{}", Self::MARKER, tokens.random_token()))
+    }
+
+    /// Whether `token` looks like a session token, which does not make it a valid one.
+    pub fn is_session_token(token: &str) -> bool {
+        token.starts_with(Self::MARKER)
+    }
+
+    pub fn hash(&self, tokens: &dyn TokenGenerator) -> String {
+        tokens.hash_token(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub token: SessionToken,
+    pub user_id: UserId,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    /// Whether the session can be used at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        now < self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use validator::{ValidateArgs, ValidationErrors};
+
+    use super::*;
+
+    fn strict() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+        }
+    }
+
+    #[rstest]
+    #[case(PasswordPolicy::default(), "correct horse", None)]
+    #[case(PasswordPolicy::default(), "short", Some("too_short"))]
+    #[case(PasswordPolicy::default(), "ぱすわーどぱすわーどぱす", None)]
+    #[case(strict(), "Passw0rd!", None)]
+    #[case(strict(), "PASSW0RD!", Some("missing_lowercase"))]
+    #[case(strict(), "passw0rd!", Some("missing_uppercase"))]
+    #[case(strict(), "Password!", Some("missing_digit"))]
+    #[case(strict(), "Passw0rd", Some("missing_symbol"))]
+    fn test_validate_password(
+        #[case] policy: PasswordPolicy,
+        #[case] password: &str,
+        #[case] code: Option<&str>,
+    ) {
+        let res = NewPassword {
+            password: password.into(),
+        }
+        .validate_args(&policy);
+
+        assert_eq!(
+            res.err()
+                .and_then(|x| x.field_errors().get("password").map(|x| x[0].code.clone())),
+            code.map(Into::into)
+        );
+    }
+
+    #[test]
+    fn test_validate_password_max_length() {
+        let res = NewPassword {
+            password: "a".repeat(MAX_PASSWORD_LENGTH as usize + 1),
+        }
+        .validate_args(&PasswordPolicy::default());
+
+        assert!(ValidationErrors::has_error(&res, "password"));
+    }
+
+    #[test]
+    fn test_debug_hides_password() {
+        let credentials = Credentials {
+            email: "taro@example.com".into(),
+            password: "secret".into(),
+        };
+        let password = NewPassword {
+            password: "secret".into(),
+        };
+
+        assert!(!format!("{:?}", credentials).contains("secret"));
+        assert!(!format!("{:?}", password).contains("secret"));
+    }
+
+    #[test]
+    fn test_session_token() {
+        let mut tokens = MockTokenGenerator::new();
+        tokens.expect_random_token().returning(|| "random".into());
+        tokens
+            .expect_hash_token()
+            .with(mockall::predicate::eq("exs_random"))
+            .returning(|_| "hash".into());
+
+        let token = SessionToken::generate(&tokens);
+
+        assert_eq!(token, SessionToken("exs_random".into()));
+        assert!(SessionToken::is_session_token(&token.0));
+        assert!(!SessionToken::is_session_token("eyJhbGciOiJIUzI1NiJ9"));
+        assert_eq!(token.hash(&tokens), "hash");
+    }
+}
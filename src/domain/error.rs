@@ -0,0 +1,52 @@
+use std::fmt;
+
+use validator::ValidationErrors;
+
+pub type Result<T, E = DomainError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DomainError {
+    #[error("not found")]
+    NotFound,
+    #[error("validation error: {0}")]
+    Validation(#[from] ValidationErrors),
+    /// The caller's version of the resource is not the current one.
+    #[error("version conflict")]
+    VersionConflict,
+    /// The email address or password is wrong, without telling which.
+    #[error("invalid credentials")]
+    InvalidCredentials,
+    /// The caller is known but may not do this.
+    #[error("forbidden")]
+    Forbidden,
+    /// A value that has to be unique is already taken.
+    #[error("{0} is already taken")]
+    UniqueViolation(Constraint),
+    /// A reference points at something that does not exist, or something still referenced was
+    /// removed.
+    #[error("{0} refers to a missing or still referenced resource")]
+    ForeignKeyViolation(Constraint),
+    /// A value got past validation but not past the storage's own checks.
+    #[error("{0} is invalid")]
+    CheckViolation(Constraint),
+    /// The change raced a concurrent one and can be retried as a whole.
+    #[error("concurrent change, try again")]
+    SerializationFailure,
+    #[error("unavailable: {0}")]
+    Unavailable(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+/// A storage constraint that rejected a change, with the field it guards if that is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub name: String,
+    pub field: Option<&'static str>,
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.field.unwrap_or(&self.name))
+    }
+}
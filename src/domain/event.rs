@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::{
+    error::Result,
+    user::{IdFormat, User, UserId},
+};
+
+/// How events write user ids. Consumers may read JSON numbers as doubles, which cannot hold ids
+/// above 2^53.
+pub const ID_FORMAT: IdFormat = IdFormat::String;
+
+pub(super) fn serialize_id<S: Serializer>(id: &UserId, serializer: S) -> Result<S::Ok, S::Error> {
+    id.formatted(ID_FORMAT).serialize(serializer)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UserCreated {
+    pub user: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UserUpdated {
+    pub user: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UserDeleted {
+    #[serde(serialize_with = "serialize_id")]
+    pub id: UserId,
+}
+
+/// Something that happened to a user that other services may react to.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum UserEvent {
+    UserCreated(UserCreated),
+    UserUpdated(UserUpdated),
+    UserDeleted(UserDeleted),
+}
+
+/// Builds the event a write raises from the user as stored after it. Usecases pass one to each
+/// repository write so the event is stored together with the change.
+pub type Raise = fn(&User) -> UserEvent;
+
+impl UserEvent {
+    pub fn created(user: &User) -> Self {
+        Self::UserCreated(UserCreated { user: user.clone() })
+    }
+
+    pub fn updated(user: &User) -> Self {
+        Self::UserUpdated(UserUpdated { user: user.clone() })
+    }
+
+    pub fn deleted(user: &User) -> Self {
+        Self::UserDeleted(UserDeleted {
+            id: user.id.clone(),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UserCreated(_) => "UserCreated",
+            Self::UserUpdated(_) => "UserUpdated",
+            Self::UserDeleted(_) => "UserDeleted",
+        }
+    }
+}
+
+/// Delivers events to whoever consumes them downstream.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(&self, event: &UserEvent) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::domain::user::Version;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize() -> anyhow::Result<()> {
+        let event = UserEvent::deleted(&User {
+            id: UserId(1),
+            name: "name".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(2013, 1, 1),
+            deleted_at: None,
+            version: Version::INITIAL,
+        });
+
+        assert_eq!(
+            serde_json::to_value(&event)?,
+            json!({"type": "UserDeleted", "id": "1"})
+        );
+        assert_eq!(event.name(), "UserDeleted");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_large_id() -> anyhow::Result<()> {
+        let event = UserEvent::created(&User {
+            id: UserId(9007199254740993),
+            name: "name".into(),
+            ..Default::default()
+        });
+
+        let json = serde_json::to_value(&event)?;
+
+        assert_eq!(json["user"]["id"], json!("9007199254740993"));
+        assert_eq!(serde_json::from_value::<UserEvent>(json)?, event);
+        // Events stored before ids were strings.
+        assert_eq!(
+            serde_json::from_value::<UserEvent>(json!({"type": "UserDeleted", "id": 1}))?,
+            UserEvent::UserDeleted(UserDeleted { id: UserId(1) })
+        );
+
+        Ok(())
+    }
+}
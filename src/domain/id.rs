@@ -0,0 +1,9 @@
+use std::fmt::Debug;
+
+use super::user::UserId;
+
+/// Source of ids for new users. Implementations must never hand out the same id twice, and should
+/// hand them out in increasing order like a sequence would.
+pub trait IdGenerator: Debug + Send + Sync {
+    fn next_id(&self) -> UserId;
+}
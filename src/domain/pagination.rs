@@ -0,0 +1,112 @@
+use chrono::NaiveDate;
+use validator::Validate;
+
+use super::user::{User, UserId};
+
+pub const DEFAULT_LIMIT: u64 = 50;
+pub const MAX_LIMIT: u64 = 1000;
+
+/// Keyset position of the last item on a page. Clients only see it as an opaque token.
+///
+/// It holds every sortable field so the same cursor works whatever the sort order is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub id: UserId,
+    pub name: String,
+    pub birth_date: Option<NaiveDate>,
+}
+
+impl From<&User> for Cursor {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id.clone(),
+            name: user.name.clone(),
+            birth_date: user.birth_date,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Validate)]
+pub struct Pagination {
+    #[validate(range(min = 1, max = "MAX_LIMIT"))]
+    pub limit: u64,
+    pub cursor: Option<Cursor>,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LIMIT,
+            cursor: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `limit + 1` fetched items; the extra one only signals
+    /// that another page exists.
+    pub fn from_overfetched(mut items: Vec<T>, limit: u64, cursor: impl Fn(&T) -> Cursor) -> Self {
+        let next_cursor = if items.len() as u64 > limit {
+            items.truncate(limit as usize);
+            items.last().map(cursor)
+        } else {
+            None
+        };
+        Self { items, next_cursor }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use validator::ValidationErrors;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, true)]
+    #[case(1, false)]
+    #[case(MAX_LIMIT, false)]
+    #[case(MAX_LIMIT + 1, true)]
+    fn test_validate_limit(#[case] limit: u64, #[case] has_error: bool) {
+        let res = Pagination {
+            limit,
+            cursor: None,
+        }
+        .validate();
+
+        assert_eq!(ValidationErrors::has_error(&res, "limit"), has_error);
+    }
+
+    #[test]
+    fn test_page_from_overfetched() {
+        let cursor = |x: &i64| Cursor {
+            id: UserId(*x),
+            name: "name".into(),
+            birth_date: None,
+        };
+
+        let page = Page::from_overfetched(vec![1, 2, 3], 2, cursor);
+
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next_cursor, Some(cursor(&2)));
+
+        let page = Page::from_overfetched(vec![1, 2], 2, cursor);
+
+        assert_eq!(page.next_cursor, None);
+    }
+}
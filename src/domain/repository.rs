@@ -1 +1,5 @@
+pub mod api_key_repository;
+pub mod audit_repository;
+pub mod credential_repository;
+pub mod role_repository;
 pub mod user_repository;
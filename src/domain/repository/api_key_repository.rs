@@ -0,0 +1,23 @@
+use crate::domain::{
+    api_key::{ApiKey, ApiKeyId, ApiKeySecret, NewApiKey},
+    error::Result,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    /// All keys including revoked ones, oldest first.
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>>;
+    /// The key with the secret, whether or not it is still active.
+    async fn find_api_key(&self, secret: &ApiKeySecret) -> Result<Option<ApiKey>>;
+    async fn create_api_key(&self, key: NewApiKey, secret: &ApiKeySecret) -> Result<ApiKey>;
+    /// Revokes the key, which is not found if it already is.
+    async fn revoke_api_key(&self, id: &ApiKeyId) -> Result<ApiKey>;
+    /// Revokes the key and creates one with the same owner, scopes and expiry under the new
+    /// secret, both or neither.
+    async fn rotate_api_key(&self, id: &ApiKeyId, secret: &ApiKeySecret) -> Result<ApiKey>;
+    /// Records that the key was used at `at`.
+    async fn touch_api_key(&self, id: &ApiKeyId, at: DateTime<Utc>) -> Result<()>;
+}
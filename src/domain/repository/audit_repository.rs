@@ -0,0 +1,9 @@
+use crate::domain::{audit::AuditEntry, error::Result, user::UserId};
+use async_trait::async_trait;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Changes to the user, oldest first.
+    async fn get_user_history(&self, id: &UserId) -> Result<Vec<AuditEntry>>;
+}
@@ -0,0 +1,25 @@
+use crate::domain::{
+    credential::{Credential, PasswordHash, Session, SessionToken, Sessions},
+    error::Result,
+    user::UserId,
+};
+use async_trait::async_trait;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait CredentialRepository: Send + Sync {
+    /// The credential of the user with the email address, unless that user is deleted or has no
+    /// password.
+    async fn find_credential_by_email(&self, email: &str) -> Result<Option<Credential>>;
+    /// Sets or replaces the user's password, revoking their sessions at the same time if asked
+    /// to. A deleted user is not found.
+    async fn set_password_hash(
+        &self,
+        id: &UserId,
+        hash: &PasswordHash,
+        sessions: Sessions,
+    ) -> Result<()>;
+    async fn create_session(&self, session: &Session) -> Result<()>;
+    /// The session with the token, expired or not, unless its user is deleted.
+    async fn find_session(&self, token: &SessionToken) -> Result<Option<Session>>;
+}
@@ -0,0 +1,13 @@
+use crate::domain::{error::Result, role::Role, user::UserId};
+use async_trait::async_trait;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    /// The user's roles, or `None` if the user does not exist or is deleted.
+    async fn find_roles(&self, id: &UserId) -> Result<Option<Vec<Role>>>;
+    /// Grants the role unless the user already has it. A deleted user is not found.
+    async fn grant_role(&self, id: &UserId, role: Role) -> Result<()>;
+    /// Fails with `NotFound` if the user does not have the role.
+    async fn revoke_role(&self, id: &UserId, role: Role) -> Result<()>;
+}
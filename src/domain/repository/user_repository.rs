@@ -1,10 +1,54 @@
-use crate::domain::user::{NewUser, User, UserId};
+use crate::domain::{
+    error::Result,
+    event::Raise,
+    pagination::{Page, Pagination},
+    user::{
+        query::UserQuery,
+        search::{SearchHit, UserSearch},
+        Deleted, NewUser, User, UserId, UserPatch, UserUpdate, Version,
+    },
+};
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 
+/// Mutations take the version the caller last read and fail with
+/// [`DomainError::VersionConflict`](crate::domain::error::DomainError::VersionConflict) if the
+/// user has changed since. Each successful mutation increments the version and stores the event
+/// built by `raise` atomically with the change.
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait UserRepository: Send + Sync {
-    async fn get_users(&self) -> anyhow::Result<Vec<User>>;
-    async fn get_user(&self, id: &UserId) -> anyhow::Result<Option<User>>;
-    async fn create_user(&self, user: NewUser) -> anyhow::Result<User>;
+    /// Lists users matching the query in its sort order, starting after the cursor.
+    async fn get_users(&self, query: &UserQuery, page: &Pagination) -> Result<Page<User>>;
+    /// All users matching the query in its sort order, read as they are consumed.
+    async fn stream_users<'a>(&'a self, query: &UserQuery) -> Result<BoxStream<'a, Result<User>>>;
+    /// Users whose name is similar to the search text, most similar first.
+    async fn search_users(&self, search: &UserSearch) -> Result<Vec<SearchHit>>;
+    async fn get_user(&self, id: &UserId, deleted: Deleted) -> Result<Option<User>>;
+    /// Those of the given normalized addresses that users already have. Deleted users keep
+    /// theirs.
+    async fn taken_emails(&self, emails: &[String]) -> Result<Vec<String>>;
+    async fn create_user(&self, user: NewUser, raise: Raise) -> Result<User>;
+    /// Creates all the users or none of them, returning them in the given order.
+    async fn create_users(&self, users: Vec<NewUser>, raise: Raise) -> Result<Vec<User>>;
+    async fn update_user(
+        &self,
+        id: &UserId,
+        version: Version,
+        user: UserUpdate,
+        raise: Raise,
+    ) -> Result<User>;
+    async fn patch_user(
+        &self,
+        id: &UserId,
+        version: Version,
+        patch: UserPatch,
+        raise: Raise,
+    ) -> Result<User>;
+    /// Marks the user as deleted. A user that is already deleted is not found.
+    async fn delete_user(&self, id: &UserId, version: Version, raise: Raise) -> Result<User>;
+    /// Clears the deletion mark.
+    async fn restore_user(&self, id: &UserId, version: Version, raise: Raise) -> Result<User>;
+    /// Removes the user permanently.
+    async fn purge_user(&self, id: &UserId) -> Result<()>;
 }
@@ -1,22 +1,226 @@
-use serde::{Deserialize, Serialize};
-use validator::Validate;
+use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use validator::{Validate, ValidationError};
+
+pub mod import;
+pub mod query;
+pub mod search;
+
+/// Serializes as a number by default; see [`UserId::formatted`] for strings. Deserializes from
+/// either form. Clients of the API only see it as a public id.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct UserId(pub i64);
 
+/// How a [`UserId`] is serialized.
+///
+/// JavaScript numbers lose precision above 2^53, so anything read by such clients writes ids as
+/// strings. Events do, see [`ID_FORMAT`](super::event::ID_FORMAT); every API version writes public
+/// ids instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdFormat {
+    #[default]
+    Number,
+    String,
+}
+
+impl UserId {
+    pub fn formatted(&self, format: IdFormat) -> FormattedUserId<'_> {
+        FormattedUserId { id: self, format }
+    }
+}
+
+/// A [`UserId`] that serializes in the chosen [`IdFormat`].
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedUserId<'a> {
+    id: &'a UserId,
+    format: IdFormat,
+}
+
+impl Serialize for FormattedUserId<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.format {
+            IdFormat::Number => serializer.serialize_i64(self.id.0),
+            IdFormat::String => serializer.collect_str(&self.id.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for UserId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdVisitor;
+
+        impl Visitor<'_> for IdVisitor {
+            type Value = UserId;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an integer or a string of one")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(UserId(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                i64::try_from(v)
+                    .map(UserId)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse()
+                    .map(UserId)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(IdVisitor)
+    }
+}
+
+/// Revision of a user, incremented on every change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Version(pub i64);
+
+impl Version {
+    pub const INITIAL: Self = Self(1);
+
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+/// The oldest age a user can be given.
+pub const MAX_AGE: u32 = 150;
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Validate)]
 pub struct User {
+    /// Written in the format of events, the only place a whole user is serialized with its id.
+    #[serde(serialize_with = "super::event::serialize_id")]
     pub id: UserId,
     #[validate(length(min = 1))]
     pub name: String,
-    pub age: u32,
+    /// Normalized with [`normalize_email`], and unique among all users including deleted ones.
+    pub email: Option<String>,
+    /// `None` if the user has not told us.
+    pub birth_date: Option<NaiveDate>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: Version,
+}
+
+/// Whether soft-deleted users are visible to a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Deleted {
+    #[default]
+    Exclude,
+    Include,
 }
 
+impl Deleted {
+    pub fn matches(&self, user: &User) -> bool {
+        matches!(self, Deleted::Include) || !user.is_deleted()
+    }
+}
+
+/// A user to create, validated with `validate_args(&today)`.
 #[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
 pub struct NewUser {
     #[validate(length(min = 1))]
     pub name: String,
-    pub age: u32,
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(custom(function = "validate_birth_date", arg = "&'v_a NaiveDate"))]
+    pub birth_date: Option<NaiveDate>,
+}
+
+/// Every field of a user, validated with `validate_args(&today)`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct UserUpdate {
+    #[validate(length(min = 1))]
+    pub name: String,
+    #[validate(email)]
+    pub email: Option<String>,
+    #[validate(custom(function = "validate_birth_date", arg = "&'v_a NaiveDate"))]
+    pub birth_date: Option<NaiveDate>,
+}
+
+/// The fields of a user to change, validated with `validate_args(&today)`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Validate)]
+pub struct UserPatch {
+    #[validate(length(min = 1))]
+    pub name: Option<String>,
+    /// `Some(None)` clears the email address, as an explicit `null` does in JSON.
+    #[serde(default, deserialize_with = "some")]
+    #[validate(email)]
+    pub email: Option<Option<String>>,
+    /// `Some(None)` clears the birth date, as an explicit `null` does in JSON.
+    #[serde(default, deserialize_with = "some")]
+    #[validate(custom(function = "validate_birth_date", arg = "&'v_a NaiveDate"))]
+    pub birth_date: Option<Option<NaiveDate>>,
+}
+
+/// Wraps a present value in `Some`, so that with `#[serde(default)]` a missing field and a `null`
+/// one can be told apart.
+fn some<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Case-folds an email address, so that addresses differing only in case belong to the same user.
+pub fn normalize_email(email: &str) -> String {
+    email.to_lowercase()
+}
+
+/// Birth dates must give an age from 0 to [`MAX_AGE`] on `today`.
+fn validate_birth_date(date: &NaiveDate, today: &NaiveDate) -> Result<(), ValidationError> {
+    match age_on(*date, *today) {
+        Some(age) if age <= MAX_AGE => Ok(()),
+        _ => Err(ValidationError::new("birth_date")),
+    }
+}
+
+/// Whole years from `birth_date` to `today`, or `None` if `today` is before `birth_date`. Someone
+/// born on 29 February turns a year older on 1 March in common years.
+pub fn age_on(birth_date: NaiveDate, today: NaiveDate) -> Option<u32> {
+    let had_birthday = (today.month(), today.day()) >= (birth_date.month(), birth_date.day());
+    let years = today.year() - birth_date.year() - i32::from(!had_birthday);
+    u32::try_from(years).ok()
+}
+
+impl User {
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// The user's age on `today`, if their birth date is known.
+    pub fn age(&self, today: NaiveDate) -> Option<u32> {
+        self.birth_date.and_then(|x| age_on(x, today))
+    }
+
+    pub fn update(&mut self, user: UserUpdate) {
+        self.name = user.name;
+        self.email = user.email.as_deref().map(normalize_email);
+        self.birth_date = user.birth_date;
+    }
+
+    pub fn patch(&mut self, patch: UserPatch) {
+        if let Some(name) = patch.name {
+            self.name = name;
+        }
+        if let Some(email) = patch.email {
+            self.email = email.as_deref().map(normalize_email);
+        }
+        if let Some(birth_date) = patch.birth_date {
+            self.birth_date = birth_date;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -24,7 +228,7 @@ mod tests {
     use pretty_assertions::assert_eq;
     use rstest::rstest;
     use serde_json::json;
-    use validator::ValidationErrors;
+    use validator::{ValidateArgs, ValidationErrors};
 
     use super::*;
 
@@ -33,7 +237,10 @@ mod tests {
             Self {
                 id: UserId(0),
                 name: Default::default(),
-                age: 0,
+                email: None,
+                birth_date: None,
+                deleted_at: None,
+                version: Version::INITIAL,
             }
         }
     }
@@ -43,7 +250,8 @@ mod tests {
         let data = json!({
             "id": 1234567890,
             "name": "Name Name",
-            "age": 100,
+            "birth_date": "1923-04-05",
+            "version": 1,
         })
         .to_string();
 
@@ -54,13 +262,75 @@ mod tests {
             User {
                 id: UserId(1234567890),
                 name: "Name Name".into(),
-                age: 100,
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 4, 5),
+                deleted_at: None,
+                version: Version::INITIAL,
             }
         );
 
         Ok(())
     }
 
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(-1)]
+    #[case((1 << 53) + 1)]
+    #[case(i64::MAX)]
+    #[case(i64::MIN)]
+    fn test_user_id_round_trip(
+        #[case] id: i64,
+        #[values(IdFormat::Number, IdFormat::String)] format: IdFormat,
+    ) -> anyhow::Result<()> {
+        let id = UserId(id);
+
+        let json = serde_json::to_string(&id.formatted(format))?;
+
+        assert_eq!(serde_json::from_str::<UserId>(&json)?, id);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(IdFormat::Number, json!(9007199254740993_i64))]
+    #[case(IdFormat::String, json!("9007199254740993"))]
+    fn test_serialize_user_id(
+        #[case] format: IdFormat,
+        #[case] expected: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let id = UserId(9007199254740993);
+
+        assert_eq!(serde_json::to_value(id.formatted(format))?, expected);
+        assert_eq!(serde_json::to_value(&id)?, json!(9007199254740993_i64));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_user_id() -> anyhow::Result<()> {
+        let expected = UserId(9007199254740993);
+
+        assert_eq!(
+            serde_json::from_value::<UserId>(json!(9007199254740993_i64))?,
+            expected
+        );
+        assert_eq!(
+            serde_json::from_value::<UserId>(json!("9007199254740993"))?,
+            expected
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(json!(""))]
+    #[case(json!("1.0"))]
+    #[case(json!("id"))]
+    #[case(json!(1.5))]
+    #[case(json!(u64::MAX))]
+    #[case(json!(null))]
+    fn test_deserialize_user_id_invalid(#[case] value: serde_json::Value) {
+        assert!(serde_json::from_value::<UserId>(value).is_err());
+    }
+
     #[rstest]
     #[case("", true)]
     #[case("a", false)]
@@ -74,4 +344,183 @@ mod tests {
 
         assert_eq!(ValidationErrors::has_error(&res, "name"), has_error);
     }
+
+    #[rstest]
+    #[case(None, false)]
+    #[case(Some(""), true)]
+    #[case(Some("a"), false)]
+    fn test_validate_patch_name(#[case] name: Option<&str>, #[case] has_error: bool) {
+        let patch = UserPatch {
+            name: name.map(Into::into),
+            ..Default::default()
+        };
+
+        let res = patch.validate_args(&date(2023, 6, 15));
+
+        assert_eq!(ValidationErrors::has_error(&res, "name"), has_error);
+    }
+
+    #[rstest]
+    #[case(None, false)]
+    #[case(Some("taro@example.com"), false)]
+    #[case(Some("Taro@Example.COM"), false)]
+    #[case(Some("taro"), true)]
+    #[case(Some("taro@"), true)]
+    #[case(Some(""), true)]
+    fn test_validate_email(#[case] email: Option<&str>, #[case] has_error: bool) {
+        let user = NewUser {
+            name: "name".into(),
+            email: email.map(Into::into),
+            birth_date: None,
+        };
+        let patch = UserPatch {
+            email: Some(email.map(Into::into)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            ValidationErrors::has_error(&user.validate_args(&date(2023, 6, 15)), "email"),
+            has_error
+        );
+        assert_eq!(
+            ValidationErrors::has_error(&patch.validate_args(&date(2023, 6, 15)), "email"),
+            has_error
+        );
+    }
+
+    #[rstest]
+    #[case("taro@example.com", "taro@example.com")]
+    #[case("Taro@Example.COM", "taro@example.com")]
+    fn test_normalize_email(#[case] email: &str, #[case] expected: &str) {
+        assert_eq!(normalize_email(email), expected);
+    }
+
+    #[test]
+    fn test_update_normalizes_email() {
+        let mut user = User::default();
+
+        user.update(UserUpdate {
+            name: "name".into(),
+            email: Some("Taro@Example.COM".into()),
+            birth_date: None,
+        });
+        assert_eq!(user.email.as_deref(), Some("taro@example.com"));
+
+        user.patch(UserPatch {
+            email: Some(Some("Jiro@Example.COM".into())),
+            ..Default::default()
+        });
+        assert_eq!(user.email.as_deref(), Some("jiro@example.com"));
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[rstest]
+    #[case(date(2000, 6, 15), date(2023, 6, 14), Some(22))]
+    #[case(date(2000, 6, 15), date(2023, 6, 15), Some(23))]
+    #[case(date(2000, 2, 29), date(2023, 2, 28), Some(22))]
+    #[case(date(2000, 2, 29), date(2023, 3, 1), Some(23))]
+    #[case(date(2000, 2, 29), date(2024, 2, 29), Some(24))]
+    #[case(date(2023, 6, 15), date(2023, 6, 15), Some(0))]
+    #[case(date(2023, 6, 16), date(2023, 6, 15), None)]
+    fn test_age_on(
+        #[case] birth_date: NaiveDate,
+        #[case] today: NaiveDate,
+        #[case] expected: Option<u32>,
+    ) {
+        assert_eq!(age_on(birth_date, today), expected);
+    }
+
+    #[test]
+    fn test_age() {
+        let user = User {
+            birth_date: Some(date(2000, 6, 15)),
+            ..Default::default()
+        };
+
+        assert_eq!(user.age(date(2023, 1, 1)), Some(22));
+        assert_eq!(User::default().age(date(2023, 1, 1)), None);
+    }
+
+    #[rstest]
+    #[case(None, false)]
+    #[case(Some(date(2000, 1, 1)), false)]
+    #[case(Some(date(2023, 6, 15)), false)]
+    #[case(Some(date(2023, 6, 16)), true)]
+    #[case(Some(date(1872, 6, 16)), false)]
+    #[case(Some(date(1872, 6, 15)), true)]
+    #[case(Some(date(1800, 1, 1)), true)]
+    fn test_validate_birth_date(#[case] birth_date: Option<NaiveDate>, #[case] has_error: bool) {
+        let today = date(2023, 6, 15);
+        let user = NewUser {
+            name: "name".into(),
+            email: None,
+            birth_date,
+        };
+        let patch = UserPatch {
+            birth_date: Some(birth_date),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            ValidationErrors::has_error(&user.validate_args(&today), "birth_date"),
+            has_error
+        );
+        assert_eq!(
+            ValidationErrors::has_error(&patch.validate_args(&today), "birth_date"),
+            has_error
+        );
+    }
+
+    #[rstest]
+    #[case(json!({}), None)]
+    #[case(json!({"birth_date": null}), Some(None))]
+    #[case(json!({"birth_date": "2000-01-02"}), Some(Some(date(2000, 1, 2))))]
+    fn test_deserialize_patch_birth_date(
+        #[case] json: serde_json::Value,
+        #[case] birth_date: Option<Option<NaiveDate>>,
+    ) -> anyhow::Result<()> {
+        assert_eq!(
+            serde_json::from_value::<UserPatch>(json)?.birth_date,
+            birth_date
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None, Some(date(2000, 1, 1)))]
+    #[case(Some(None), None)]
+    #[case(Some(Some(date(2001, 2, 3))), Some(date(2001, 2, 3)))]
+    fn test_patch(
+        #[case] birth_date: Option<Option<NaiveDate>>,
+        #[case] expected: Option<NaiveDate>,
+    ) {
+        let mut user = User {
+            id: UserId(1),
+            name: "Name".into(),
+            email: None,
+            birth_date: Some(date(2000, 1, 1)),
+            deleted_at: None,
+            version: Version::INITIAL,
+        };
+
+        user.patch(UserPatch {
+            birth_date,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            user,
+            User {
+                id: UserId(1),
+                name: "Name".into(),
+                email: None,
+                birth_date: expected,
+                deleted_at: None,
+                version: Version::INITIAL,
+            }
+        );
+    }
 }
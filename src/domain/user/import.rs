@@ -0,0 +1,32 @@
+use serde::Serialize;
+
+use super::NewUser;
+
+/// A user read from line `line` of an import file, or why it could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRow {
+    pub line: u64,
+    pub user: Result<NewUser, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportError {
+    pub line: u64,
+    pub message: String,
+}
+
+/// Outcome of an import. An import is all or nothing, so `imported` is zero unless `errors` is
+/// empty.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct ImportReport {
+    pub rows: usize,
+    pub imported: usize,
+    pub dry_run: bool,
+    pub errors: Vec<ImportError>,
+}
+
+impl ImportReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
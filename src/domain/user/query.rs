@@ -0,0 +1,293 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    ops::{Bound, RangeBounds},
+};
+
+use chrono::{Months, NaiveDate};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::domain::{error::DomainError, pagination::Cursor};
+
+use super::{Deleted, User, MAX_AGE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Id,
+    Name,
+    Age,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Sort {
+    pub fn asc(field: SortField) -> Self {
+        Self {
+            field,
+            descending: false,
+        }
+    }
+
+    pub fn desc(field: SortField) -> Self {
+        Self {
+            field,
+            descending: true,
+        }
+    }
+
+    /// Parses a comma separated list such as `-age,name`, where `-` means descending.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, DomainError> {
+        s.split(',')
+            .map(|x| {
+                let (descending, name) = match x.strip_prefix('-') {
+                    Some(name) => (true, name),
+                    None => (false, x),
+                };
+                let field = match name {
+                    "id" => SortField::Id,
+                    "name" => SortField::Name,
+                    "age" => SortField::Age,
+                    _ => {
+                        let mut error = ValidationError::new("sort");
+                        error.add_param("value".into(), &x);
+                        let mut errors = ValidationErrors::new();
+                        errors.add("sort", error);
+                        return Err(errors.into());
+                    }
+                };
+                Ok(Self { field, descending })
+            })
+            .collect()
+    }
+
+    fn compare(&self, a: &Cursor, b: &Cursor) -> Ordering {
+        let ord = match self.field {
+            SortField::Id => a.id.0.cmp(&b.id.0),
+            SortField::Name => a.name.cmp(&b.name),
+            // Younger means born later. Unknown ages come first, as with `Option`.
+            SortField::Age => a.birth_date.map(Reverse).cmp(&b.birth_date.map(Reverse)),
+        };
+        if self.descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+}
+
+/// Filter and sort specification for listing users.
+#[derive(Debug, Clone, PartialEq, Default, Validate)]
+#[validate(schema(function = "validate_age_range"))]
+pub struct UserQuery {
+    pub deleted: Deleted,
+    #[validate(length(min = 1))]
+    pub name_prefix: Option<String>,
+    #[validate(range(max = "MAX_AGE"))]
+    pub min_age: Option<u32>,
+    #[validate(range(max = "MAX_AGE"))]
+    pub max_age: Option<u32>,
+    pub sort: Vec<Sort>,
+}
+
+impl UserQuery {
+    /// Users without a birth date match no age range.
+    pub fn matches(&self, user: &User, today: NaiveDate) -> bool {
+        self.deleted.matches(user)
+            && self
+                .name_prefix
+                .as_ref()
+                .map_or(true, |x| user.name.starts_with(x.as_str()))
+            && (self.min_age.is_none() && self.max_age.is_none()
+                || user
+                    .birth_date
+                    .map_or(false, |x| self.birth_date_range(today).contains(&x)))
+    }
+
+    /// The birth dates of users whose age on `today` is within the age range.
+    pub fn birth_date_range(&self, today: NaiveDate) -> (Bound<NaiveDate>, Bound<NaiveDate>) {
+        (
+            self.max_age.map_or(Bound::Unbounded, |x| {
+                Bound::Excluded(years_before(today, x + 1))
+            }),
+            self.min_age.map_or(Bound::Unbounded, |x| {
+                Bound::Included(years_before(today, x))
+            }),
+        )
+    }
+
+    /// The requested sort keys, with `id` appended as the final tiebreaker so the order is total.
+    pub fn sort_keys(&self) -> Vec<Sort> {
+        let mut keys = self.sort.clone();
+        if !keys.iter().any(|x| x.field == SortField::Id) {
+            keys.push(Sort::asc(SortField::Id));
+        }
+        keys
+    }
+
+    pub fn compare(&self, a: &Cursor, b: &Cursor) -> Ordering {
+        self.sort_keys()
+            .iter()
+            .map(|x| x.compare(a, b))
+            .find(|x| x.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// The latest birth date of someone who is `years` old on `today`.
+fn years_before(today: NaiveDate, years: u32) -> NaiveDate {
+    today
+        .checked_sub_months(Months::new(years * 12))
+        .unwrap_or(NaiveDate::MIN)
+}
+
+fn validate_age_range(query: &UserQuery) -> Result<(), ValidationError> {
+    match (query.min_age, query.max_age) {
+        (Some(min), Some(max)) if min > max => Err(ValidationError::new("age_range")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::user::{age_on, UserId, Version};
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// A user who is `age` on 2023-06-15.
+    fn user(id: i64, name: &str, age: i32) -> User {
+        User {
+            id: UserId(id),
+            name: name.into(),
+            email: None,
+            birth_date: Some(date(2023 - age, 1, 1)),
+            deleted_at: None,
+            version: Version::INITIAL,
+        }
+    }
+
+    #[test]
+    fn test_parse_sort() -> anyhow::Result<()> {
+        assert_eq!(
+            Sort::parse_list("-age,name")?,
+            vec![Sort::desc(SortField::Age), Sort::asc(SortField::Name)]
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("email")]
+    #[case("age,-")]
+    fn test_parse_sort_invalid(#[case] s: &str) {
+        assert_matches!(Sort::parse_list(s), Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "sort"));
+        });
+    }
+
+    #[rstest]
+    #[case(UserQuery::default(), true)]
+    #[case(UserQuery { name_prefix: Some("Ta".into()), ..Default::default() }, true)]
+    #[case(UserQuery { name_prefix: Some("ta".into()), ..Default::default() }, false)]
+    #[case(UserQuery { min_age: Some(20), max_age: Some(20), ..Default::default() }, true)]
+    #[case(UserQuery { min_age: Some(21), ..Default::default() }, false)]
+    #[case(UserQuery { max_age: Some(19), ..Default::default() }, false)]
+    fn test_matches(#[case] query: UserQuery, #[case] expected: bool) {
+        assert_eq!(
+            query.matches(&user(1, "Taro", 20), date(2023, 6, 15)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(UserQuery::default(), true)]
+    #[case(UserQuery { min_age: Some(0), ..Default::default() }, false)]
+    fn test_matches_without_birth_date(#[case] query: UserQuery, #[case] expected: bool) {
+        let user = User {
+            birth_date: None,
+            ..user(1, "Taro", 20)
+        };
+
+        assert_eq!(query.matches(&user, date(2023, 6, 15)), expected);
+    }
+
+    #[rstest]
+    #[case(date(2023, 6, 15))]
+    #[case(date(2024, 2, 29))]
+    #[case(date(2023, 2, 28))]
+    #[case(date(2023, 3, 1))]
+    fn test_birth_date_range_agrees_with_age(#[case] today: NaiveDate) {
+        let query = UserQuery {
+            min_age: Some(20),
+            max_age: Some(22),
+            ..Default::default()
+        };
+        let range = query.birth_date_range(today);
+
+        let mut birth_date = date(1999, 1, 1);
+        while birth_date < date(2005, 1, 1) {
+            let age = age_on(birth_date, today).unwrap();
+            assert_eq!(
+                range.contains(&birth_date),
+                (20..=22).contains(&age),
+                "born {} on {}",
+                birth_date,
+                today
+            );
+            birth_date = birth_date.succ_opt().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_validate_max_age() {
+        let query = UserQuery {
+            max_age: Some(MAX_AGE + 1),
+            ..Default::default()
+        };
+
+        assert!(ValidationErrors::has_error(&query.validate(), "max_age"));
+    }
+
+    #[test]
+    fn test_validate_age_range() {
+        let query = UserQuery {
+            min_age: Some(30),
+            max_age: Some(20),
+            ..Default::default()
+        };
+
+        assert!(ValidationErrors::has_error(&query.validate(), "__all__"));
+    }
+
+    #[test]
+    fn test_compare() {
+        let query = UserQuery {
+            sort: vec![Sort::desc(SortField::Age), Sort::asc(SortField::Name)],
+            ..Default::default()
+        };
+        let mut users = vec![
+            user(1, "b", 20),
+            user(2, "a", 20),
+            user(3, "c", 30),
+            user(4, "a", 20),
+        ];
+
+        users.sort_by(|a, b| query.compare(&a.into(), &b.into()));
+
+        assert_eq!(
+            users.into_iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![3, 2, 4, 1]
+        );
+    }
+}
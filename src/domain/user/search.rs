@@ -0,0 +1,51 @@
+use serde::Serialize;
+use validator::Validate;
+
+use crate::domain::pagination::{DEFAULT_LIMIT, MAX_LIMIT};
+
+use super::User;
+
+/// Minimum similarity for a name to match, the default of `pg_trgm.similarity_threshold`.
+pub const SIMILARITY_THRESHOLD: f32 = 0.3;
+
+/// Fuzzy name search. Soft-deleted users are never returned.
+#[derive(Debug, Clone, PartialEq, Validate)]
+pub struct UserSearch {
+    #[validate(length(min = 1))]
+    pub q: String,
+    #[validate(range(min = 1, max = "MAX_LIMIT"))]
+    pub limit: u64,
+}
+
+impl UserSearch {
+    pub fn new(q: impl Into<String>) -> Self {
+        Self {
+            q: q.into(),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+/// A user matching a search, with its trigram similarity to the search text in `0.0..=1.0`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub user: User,
+    pub score: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use validator::ValidationErrors;
+
+    use super::*;
+
+    #[rstest]
+    #[case(UserSearch::new(""), "q")]
+    #[case(UserSearch { limit: 0, ..UserSearch::new("q") }, "limit")]
+    #[case(UserSearch { limit: MAX_LIMIT + 1, ..UserSearch::new("q") }, "limit")]
+    fn test_validate(#[case] search: UserSearch, #[case] field: &'static str) {
+        assert!(ValidationErrors::has_error(&search.validate(), field));
+    }
+}
@@ -1 +1,7 @@
+pub mod clock;
+pub mod event;
+pub mod id;
+pub mod import;
+pub mod password;
 pub mod repository;
+pub mod token;
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::clock::Clock;
+
+/// The current time in UTC.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -0,0 +1,17 @@
+use crate::domain::{
+    error::Result,
+    event::{EventPublisher, UserEvent},
+};
+
+/// Writes published events to the log, for local runs without a message broker.
+#[derive(Debug, Clone, Default)]
+pub struct LogPublisher;
+
+#[async_trait::async_trait]
+impl EventPublisher for LogPublisher {
+    async fn publish(&self, event: &UserEvent) -> Result<()> {
+        let payload = serde_json::to_string(event).map_err(anyhow::Error::from)?;
+        tracing::info!("published {}: {}", event.name(), payload);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::domain::{
+    error::Result,
+    event::{EventPublisher, UserEvent},
+};
+
+/// Keeps published events in memory, for tests and local runs.
+#[derive(Debug, Clone, Default)]
+pub struct OnMemoryPublisher {
+    events: Arc<Mutex<Vec<UserEvent>>>,
+}
+
+impl OnMemoryPublisher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Events published so far, oldest first.
+    pub async fn events(&self) -> Vec<UserEvent> {
+        self.events.lock().await.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventPublisher for OnMemoryPublisher {
+    async fn publish(&self, event: &UserEvent) -> Result<()> {
+        self.events.lock().await.push(event.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+pub mod sequential;
+pub mod snowflake;
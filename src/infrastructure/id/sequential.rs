@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use crate::domain::{id::IdGenerator, user::UserId};
+
+/// Counts up from 1, as a `BIGSERIAL` column does.
+#[derive(Debug)]
+pub struct SequentialIds {
+    next: AtomicI64,
+}
+
+impl SequentialIds {
+    pub fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    pub fn starting_at(first: i64) -> Self {
+        Self {
+            next: AtomicI64::new(first),
+        }
+    }
+}
+
+impl Default for SequentialIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> UserId {
+        UserId(self.next.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_next_id() {
+        let ids = SequentialIds::starting_at(10);
+
+        assert_eq!(ids.next_id(), UserId(10));
+        assert_eq!(ids.next_id(), UserId(11));
+        assert_eq!(SequentialIds::new().next_id(), UserId(1));
+    }
+}
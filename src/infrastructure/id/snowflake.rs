@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+
+use chrono::Utc;
+
+use crate::domain::{id::IdGenerator, user::UserId};
+
+/// 2023-01-01T00:00:00Z, the zero of the timestamp part, in milliseconds since the Unix epoch.
+pub const EPOCH: i64 = 1_672_531_200_000;
+
+const NODE_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+pub const MAX_NODE: u16 = (1 << NODE_BITS) - 1;
+const MAX_SEQUENCE: i64 = (1 << SEQUENCE_BITS) - 1;
+
+/// Time ordered ids in the layout of Twitter's Snowflake: 41 bits of milliseconds since
+/// [`EPOCH`], 10 bits of node and 12 bits of sequence within the millisecond.
+///
+/// Each process that creates users needs its own node. When a millisecond's sequence runs out,
+/// or the clock goes backwards, ids are taken from the following millisecond instead of waiting.
+#[derive(Debug)]
+pub struct SnowflakeIds {
+    node: i64,
+    clock: fn() -> i64,
+    /// The millisecond and sequence of the last id.
+    last: Mutex<(i64, i64)>,
+}
+
+impl SnowflakeIds {
+    /// # Panics
+    ///
+    /// If `node` is greater than [`MAX_NODE`].
+    pub fn new(node: u16) -> Self {
+        Self::with_clock(node, || Utc::now().timestamp_millis())
+    }
+
+    /// Ids timed by `clock`, which returns milliseconds since the Unix epoch.
+    pub fn with_clock(node: u16, clock: fn() -> i64) -> Self {
+        assert!(node <= MAX_NODE, "node must be at most {}", MAX_NODE);
+        Self {
+            node: node.into(),
+            clock,
+            last: Mutex::new((i64::MIN, 0)),
+        }
+    }
+}
+
+impl IdGenerator for SnowflakeIds {
+    fn next_id(&self) -> UserId {
+        let mut last = self.last.lock().unwrap();
+        let now = (self.clock)() - EPOCH;
+        *last = match *last {
+            (millis, sequence) if now <= millis && sequence < MAX_SEQUENCE => {
+                (millis, sequence + 1)
+            }
+            (millis, _) if now <= millis => (millis + 1, 0),
+            _ => (now, 0),
+        };
+        let (millis, sequence) = *last;
+        UserId(millis << (NODE_BITS + SEQUENCE_BITS) | self.node << SEQUENCE_BITS | sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_next_id() {
+        let ids = SnowflakeIds::with_clock(3, || EPOCH + 5);
+
+        assert_eq!(ids.next_id(), UserId(5 << 22 | 3 << 12));
+        assert_eq!(ids.next_id(), UserId(5 << 22 | 3 << 12 | 1));
+    }
+
+    #[test]
+    fn test_next_id_if_sequence_exhausted() {
+        let ids = SnowflakeIds::with_clock(0, || EPOCH + 5);
+
+        let last = (0..=MAX_SEQUENCE).map(|_| ids.next_id()).last();
+
+        assert_eq!(last, Some(UserId(5 << 22 | MAX_SEQUENCE)));
+        assert_eq!(ids.next_id(), UserId(6 << 22));
+    }
+
+    #[test]
+    fn test_next_id_ordered() {
+        let ids = SnowflakeIds::new(MAX_NODE);
+
+        let a = ids.next_id();
+        let b = ids.next_id();
+
+        assert!(a.0 > 0);
+        assert!(a.0 < b.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_if_node_too_large() {
+        SnowflakeIds::new(MAX_NODE + 1);
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    iter,
+    str::FromStr,
+};
+
+use csv::{ReaderBuilder, Trim};
+use futures::{stream, Stream};
+use serde::Deserialize;
+use tokio::{io::AsyncRead, sync::mpsc, task};
+use tokio_util::io::SyncIoBridge;
+
+use crate::domain::user::{import::ImportRow, NewUser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Comma separated values with a `name,email,birth_date` header, where `email` and
+    /// `birth_date` may be left out.
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "ndjson" => Ok(Self::Ndjson),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+/// Reads users one line at a time, so the input is never loaded as a whole. Unreadable lines are
+/// returned as errors rather than ending the iteration.
+pub fn read_users<'a>(
+    format: Format,
+    reader: impl Read + Send + 'a,
+) -> Box<dyn Iterator<Item = ImportRow> + Send + 'a> {
+    match format {
+        Format::Csv => read_csv(reader),
+        Format::Ndjson => read_ndjson(reader),
+    }
+}
+
+/// How many parsed rows may wait for the consumer of [`stream_users`].
+const STREAM_BUFFER: usize = 64;
+
+/// [`read_users`] for async readers. Parsing blocks, so it runs on a blocking thread that reads
+/// only as fast as the rows are consumed.
+pub fn stream_users(
+    format: Format,
+    reader: impl AsyncRead + Send + Unpin + 'static,
+) -> impl Stream<Item = ImportRow> + Send {
+    let reader = SyncIoBridge::new(reader);
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    task::spawn_blocking(move || {
+        for row in read_users(format, reader) {
+            // The consumer is gone, so nobody wants the rest.
+            if tx.blocking_send(row).is_err() {
+                break;
+            }
+        }
+    });
+    stream::unfold(rx, |mut rx| async { rx.recv().await.map(|x| (x, rx)) })
+}
+
+fn read_csv<'a>(reader: impl Read + Send + 'a) -> Box<dyn Iterator<Item = ImportRow> + Send + 'a> {
+    let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(reader);
+    let headers = match reader.headers() {
+        Ok(x) => x.clone(),
+        Err(e) => {
+            return Box::new(iter::once(ImportRow {
+                line: 1,
+                user: Err(e.to_string()),
+            }))
+        }
+    };
+    Box::new(reader.into_records().map(move |x| {
+        match x {
+            Ok(record) => ImportRow {
+                line: record.position().map_or(0, |x| x.line()),
+                user: record
+                    .deserialize::<NewUser>(Some(&headers))
+                    .map_err(|e| e.to_string()),
+            },
+            Err(e) => ImportRow {
+                line: e.position().map_or(0, |x| x.line()),
+                user: Err(e.to_string()),
+            },
+        }
+    }))
+}
+
+fn read_ndjson<'a>(
+    reader: impl Read + Send + 'a,
+) -> Box<dyn Iterator<Item = ImportRow> + Send + 'a> {
+    Box::new(
+        BufReader::new(reader)
+            .lines()
+            .zip(1..)
+            .filter(|(x, _)| !matches!(x, Ok(x) if x.trim().is_empty()))
+            .map(|(x, line)| ImportRow {
+                line,
+                user: x
+                    .map_err(|e| e.to_string())
+                    .and_then(|x| serde_json::from_str(&x).map_err(|e| e.to_string())),
+            }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use futures::StreamExt;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn new_user(
+        name: &str,
+        email: Option<&str>,
+        birth_date: Option<&str>,
+    ) -> Result<NewUser, String> {
+        Ok(NewUser {
+            name: name.into(),
+            email: email.map(Into::into),
+            birth_date: birth_date.map(|x| x.parse().unwrap()),
+        })
+    }
+
+    #[rstest]
+    #[case(
+        Format::Csv,
+        "name,email,birth_date\nTaro, taro@example.com, 2003-01-02\nHanako,,\n",
+        Some("taro@example.com"),
+        vec![2, 3]
+    )]
+    #[case(Format::Csv, "name,birth_date\nTaro, 2003-01-02\nHanako,\n", None, vec![2, 3])]
+    #[case(
+        Format::Ndjson,
+        "{\"name\":\"Taro\",\"email\":\"taro@example.com\",\"birth_date\":\"2003-01-02\"}\n\n{\"name\":\"Hanako\"}\n",
+        Some("taro@example.com"),
+        vec![1, 3]
+    )]
+    fn test_read_users(
+        #[case] format: Format,
+        #[case] input: &str,
+        #[case] email: Option<&str>,
+        #[case] lines: Vec<u64>,
+    ) {
+        let rows = read_users(format, input.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(
+            rows.iter().map(|x| x.user.clone()).collect::<Vec<_>>(),
+            vec![
+                new_user("Taro", email, Some("2003-01-02")),
+                new_user("Hanako", None, None)
+            ]
+        );
+        assert_eq!(rows.iter().map(|x| x.line).collect::<Vec<_>>(), lines);
+    }
+
+    #[rstest]
+    #[case(
+        Format::Csv,
+        "name,birth_date\nTaro,2003-13-01\nHanako\nJiro,1983-01-02\n"
+    )]
+    #[case(Format::Ndjson, "{\"name\":\"Taro\",\"birth_date\":\"twenty\"}\n{\"name\":\"Hanako\"\n{\"name\":\"Jiro\",\"birth_date\":\"1983-01-02\"}\n")]
+    fn test_read_users_invalid(#[case] format: Format, #[case] input: &str) {
+        let rows = read_users(format, input.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(rows.len(), 3);
+        assert_matches!(&rows[0].user, Err(_));
+        assert_matches!(&rows[1].user, Err(_));
+        assert_eq!(rows[2].user, new_user("Jiro", None, Some("1983-01-02")));
+    }
+
+    #[tokio::test]
+    async fn test_stream_users() {
+        let input = "name,birth_date\nTaro,2003-01-02\nHanako,\n";
+
+        let rows = stream_users(Format::Csv, input.as_bytes())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            rows.into_iter()
+                .map(|x| (x.line, x.user))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, new_user("Taro", None, Some("2003-01-02"))),
+                (3, new_user("Hanako", None, None))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("ndjson".parse(), Ok(Format::Ndjson));
+        assert_matches!("json".parse::<Format>(), Err(_));
+    }
+}
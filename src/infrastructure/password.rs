@@ -0,0 +1,135 @@
+use argon2::{
+    password_hash::{self, PasswordHasher as _, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use rand::rngs::OsRng;
+
+use crate::domain::{
+    credential::{PasswordHash, PasswordHasher},
+    error::Result,
+};
+
+/// Argon2id in the PHC string format, with a random salt per hash.
+#[derive(Debug, Clone)]
+pub struct Argon2Hasher {
+    params: Params,
+}
+
+impl Argon2Hasher {
+    pub fn new(params: Params) -> Self {
+        Self { params }
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl PasswordHasher for Argon2Hasher {
+    async fn hash(&self, password: &str) -> Result<PasswordHash> {
+        let (argon2, password) = (self.argon2(), password.to_owned());
+        blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+            let hash = argon2
+                .hash_password(password.as_bytes(), &salt)
+                .map_err(|e| anyhow::anyhow!("hash password: {}", e))?;
+            Ok(PasswordHash(hash.to_string()))
+        })
+        .await
+    }
+
+    async fn verify(&self, password: &str, hash: &PasswordHash) -> Result<bool> {
+        let (argon2, password, hash) = (self.argon2(), password.to_owned(), hash.0.clone());
+        blocking(move || {
+            let hash = password_hash::PasswordHash::new(&hash)
+                .map_err(|e| anyhow::anyhow!("parse password hash: {}", e))?;
+            // The hash carries its own parameters, so ones made with older costs still verify.
+            match argon2.verify_password(password.as_bytes(), &hash) {
+                Ok(()) => Ok(true),
+                Err(password_hash::Error::Password) => Ok(false),
+                Err(e) => Err(anyhow::anyhow!("verify password: {}", e).into()),
+            }
+        })
+        .await
+    }
+
+    fn needs_rehash(&self, hash: &PasswordHash) -> bool {
+        let Ok(hash) = password_hash::PasswordHash::new(&hash.0) else {
+            return true;
+        };
+        let Ok(params) = Params::try_from(&hash) else {
+            return true;
+        };
+        hash.algorithm != Algorithm::Argon2id.ident()
+            || hash.version != Some(Version::V0x13.into())
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+    }
+}
+
+/// Runs `f` on the blocking thread pool, as hashing would hold up every task on a runtime thread.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(anyhow::Error::from)?
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn hasher(t_cost: u32) -> Argon2Hasher {
+        Argon2Hasher::new(Params::new(Params::MIN_M_COST, t_cost, 1, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_hash_and_verify() -> anyhow::Result<()> {
+        let hasher = hasher(1);
+        let hash = hasher.hash("correct horse").await?;
+
+        assert!(hash.0.starts_with("$argon2id$v=19$m=8,t=1,p=1$"));
+        assert_ne!(hash, hasher.hash("correct horse").await?);
+        assert!(hasher.verify("correct horse", &hash).await?);
+        assert!(!hasher.verify("wrong horse", &hash).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_other_params() -> anyhow::Result<()> {
+        let hash = hasher(2).hash("correct horse").await?;
+
+        assert!(hasher(1).verify("correct horse", &hash).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_malformed_hash() {
+        assert!(hasher(1)
+            .verify("correct horse", &PasswordHash("plain".into()))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_needs_rehash() -> anyhow::Result<()> {
+        let hash = hasher(1).hash("correct horse").await?;
+
+        assert_eq!(hasher(1).needs_rehash(&hash), false);
+        assert_eq!(hasher(2).needs_rehash(&hash), true);
+        assert_eq!(
+            hasher(1).needs_rehash(&PasswordHash(
+                "$argon2i$v=19$m=8,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNoaGFzaA".into()
+            )),
+            true
+        );
+        assert_eq!(hasher(1).needs_rehash(&PasswordHash("plain".into())), true);
+
+        Ok(())
+    }
+}
@@ -1,61 +1,314 @@
 use std::sync::Arc;
 
-use rand::random;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
 use tokio::sync::Mutex;
-use validator::Validate;
+use validator::ValidateArgs;
 
-use crate::domain::{
-    repository::user_repository::UserRepository,
-    user::{NewUser, User, UserId},
+use crate::{
+    domain::{
+        clock::Clock,
+        error::{Constraint, DomainError, Result},
+        event::{Raise, UserEvent},
+        id::IdGenerator,
+        pagination::{Cursor, Page, Pagination},
+        repository::user_repository::UserRepository,
+        user::{
+            normalize_email,
+            query::UserQuery,
+            search::{SearchHit, UserSearch, SIMILARITY_THRESHOLD},
+            Deleted, NewUser, User, UserId, UserPatch, UserUpdate, Version,
+        },
+    },
+    infrastructure::{clock::SystemClock, id::sequential::SequentialIds},
 };
 
-#[derive(Debug, Clone, Default)]
+mod trigram;
+
+#[derive(Debug, Clone)]
 pub struct OnMemoryRepository {
     users: Arc<Mutex<Vec<User>>>,
+    events: Arc<Mutex<Vec<UserEvent>>>,
+    ids: Arc<dyn IdGenerator>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for OnMemoryRepository {
+    fn default() -> Self {
+        Self {
+            users: Default::default(),
+            events: Default::default(),
+            ids: Arc::new(SequentialIds::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
 }
 
 impl OnMemoryRepository {
+    /// A repository numbering users from 1.
     pub fn new() -> Self {
         Default::default()
     }
+
+    pub fn with_id_generator(self, ids: impl IdGenerator + 'static) -> Self {
+        Self {
+            ids: Arc::new(ids),
+            ..self
+        }
+    }
+
+    /// Computes ages for age range queries on the day `clock` says it is.
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Events raised by writes so far, oldest first.
+    pub async fn events(&self) -> Vec<UserEvent> {
+        self.events.lock().await.clone()
+    }
+
+    fn new_user(&self, user: NewUser) -> Result<User> {
+        user.validate_args(&self.clock.today())?;
+        Ok(User {
+            id: self.ids.next_id(),
+            name: user.name,
+            email: user.email.as_deref().map(normalize_email),
+            birth_date: user.birth_date,
+            deleted_at: None,
+            version: Version::INITIAL,
+        })
+    }
+
+    async fn modify(
+        &self,
+        id: &UserId,
+        version: Version,
+        deleted: Deleted,
+        raise: Raise,
+        f: impl FnOnce(&mut User),
+    ) -> Result<User> {
+        let mut users = self.users.lock().await;
+        let i = users
+            .iter()
+            .position(|x| x.id == *id && deleted.matches(x))
+            .ok_or(DomainError::NotFound)?;
+        if users[i].version != version {
+            return Err(DomainError::VersionConflict);
+        }
+        let mut user = users[i].clone();
+        f(&mut user);
+        check_unique_email(&users, &user)?;
+        user.version = version.next();
+        self.events.lock().await.push(raise(&user));
+        users[i] = user.clone();
+        Ok(user)
+    }
 }
 
 #[async_trait::async_trait]
 impl UserRepository for OnMemoryRepository {
-    async fn get_users(&self) -> anyhow::Result<Vec<User>> {
-        Ok(self.users.lock().await.clone())
+    async fn get_users(&self, query: &UserQuery, page: &Pagination) -> Result<Page<User>> {
+        let today = self.clock.today();
+        let mut users = self
+            .users
+            .lock()
+            .await
+            .iter()
+            .filter(|x| query.matches(x, today))
+            .filter(|x| {
+                page.cursor
+                    .as_ref()
+                    .map_or(true, |c| query.compare(&Cursor::from(*x), c).is_gt())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        users.sort_by(|a, b| query.compare(&a.into(), &b.into()));
+        users.truncate(page.limit as usize + 1);
+
+        Ok(Page::from_overfetched(users, page.limit, |x| {
+            Cursor::from(x)
+        }))
     }
 
-    async fn get_user(&self, id: &UserId) -> anyhow::Result<Option<User>> {
-        Ok(self
+    async fn stream_users<'a>(&'a self, query: &UserQuery) -> Result<BoxStream<'a, Result<User>>> {
+        let today = self.clock.today();
+        let mut users = self
             .users
             .lock()
             .await
             .iter()
-            .filter(|x| x.id == *id)
+            .filter(|x| query.matches(x, today))
             .cloned()
-            .take(1)
-            .collect::<Vec<_>>()
-            .pop())
+            .collect::<Vec<_>>();
+        users.sort_by(|a, b| query.compare(&a.into(), &b.into()));
+
+        Ok(stream::iter(users.into_iter().map(Ok)).boxed())
     }
 
-    async fn create_user(&self, user: NewUser) -> anyhow::Result<User> {
-        let user = User {
-            id: UserId(random::<u32>() as i64),
-            name: user.name,
-            age: user.age,
-        };
-        user.validate()?;
-        self.users.lock().await.push(user.clone());
+    async fn search_users(&self, search: &UserSearch) -> Result<Vec<SearchHit>> {
+        let mut hits = self
+            .users
+            .lock()
+            .await
+            .iter()
+            .filter(|x| !x.is_deleted())
+            .map(|x| SearchHit {
+                user: x.clone(),
+                score: trigram::similarity(&x.name, &search.q),
+            })
+            .filter(|x| x.score >= SIMILARITY_THRESHOLD)
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.user.id.0.cmp(&b.user.id.0))
+        });
+        hits.truncate(search.limit as usize);
+
+        Ok(hits)
+    }
+
+    async fn get_user(&self, id: &UserId, deleted: Deleted) -> Result<Option<User>> {
+        Ok(self
+            .users
+            .lock()
+            .await
+            .iter()
+            .find(|x| x.id == *id && deleted.matches(x))
+            .cloned())
+    }
+
+    async fn taken_emails(&self, emails: &[String]) -> Result<Vec<String>> {
+        Ok(self
+            .users
+            .lock()
+            .await
+            .iter()
+            .filter_map(|x| x.email.clone())
+            .filter(|x| emails.contains(x))
+            .collect())
+    }
+
+    async fn create_user(&self, user: NewUser, raise: Raise) -> Result<User> {
+        let user = self.new_user(user)?;
+        let mut users = self.users.lock().await;
+        check_unique(&users, &user)?;
+        self.events.lock().await.push(raise(&user));
+        users.push(user.clone());
 
         Ok(user)
     }
+
+    async fn create_users(&self, users: Vec<NewUser>, raise: Raise) -> Result<Vec<User>> {
+        let created = users
+            .into_iter()
+            .map(|x| self.new_user(x))
+            .collect::<Result<Vec<_>>>()?;
+        let mut users = self.users.lock().await;
+        for (i, user) in created.iter().enumerate() {
+            check_unique(&users, user)?;
+            check_unique(&created[..i], user)?;
+        }
+        self.events.lock().await.extend(created.iter().map(raise));
+        users.extend(created.iter().cloned());
+
+        Ok(created)
+    }
+
+    async fn update_user(
+        &self,
+        id: &UserId,
+        version: Version,
+        user: UserUpdate,
+        raise: Raise,
+    ) -> Result<User> {
+        user.validate_args(&self.clock.today())?;
+        self.modify(id, version, Deleted::Exclude, raise, |x| x.update(user))
+            .await
+    }
+
+    async fn patch_user(
+        &self,
+        id: &UserId,
+        version: Version,
+        patch: UserPatch,
+        raise: Raise,
+    ) -> Result<User> {
+        patch.validate_args(&self.clock.today())?;
+        self.modify(id, version, Deleted::Exclude, raise, |x| x.patch(patch))
+            .await
+    }
+
+    async fn delete_user(&self, id: &UserId, version: Version, raise: Raise) -> Result<User> {
+        let now = self.clock.now();
+        self.modify(id, version, Deleted::Exclude, raise, |x| {
+            x.deleted_at = Some(now)
+        })
+        .await
+    }
+
+    async fn restore_user(&self, id: &UserId, version: Version, raise: Raise) -> Result<User> {
+        self.modify(id, version, Deleted::Include, raise, |x| {
+            x.deleted_at = None
+        })
+        .await
+    }
+
+    async fn purge_user(&self, id: &UserId) -> Result<()> {
+        let mut users = self.users.lock().await;
+        let len = users.len();
+        users.retain(|x| x.id != *id);
+        if users.len() == len {
+            return Err(DomainError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+/// Reports a taken id as the database would, as a violation of the primary key.
+fn check_unique(users: &[User], user: &User) -> Result<()> {
+    if users.iter().any(|x| x.id == user.id) {
+        return Err(DomainError::UniqueViolation(Constraint {
+            name: "users_pkey".into(),
+            field: None,
+        }));
+    }
+    check_unique_email(users, user)
+}
+
+/// Like the unique index in the database, whose name it reports, deleted users keep their
+/// addresses.
+fn check_unique_email(users: &[User], user: &User) -> Result<()> {
+    if user.email.is_some()
+        && users
+            .iter()
+            .any(|x| x.id != user.id && x.email == user.email)
+    {
+        return Err(DomainError::UniqueViolation(Constraint {
+            name: "users_email_key".into(),
+            field: Some("email"),
+        }));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::user::{NewUser, UserId};
+    use crate::domain::{
+        clock::{Clock, FixedClock},
+        user::{
+            query::{Sort, SortField},
+            Deleted, NewUser, UserId, UserPatch, UserUpdate,
+        },
+    };
     use assert_matches::assert_matches;
+    use chrono::{NaiveDate, Utc};
+    use futures::TryStreamExt;
     use pretty_assertions::assert_eq;
 
     use super::*;
@@ -65,15 +318,178 @@ mod tests {
         let users = vec![User {
             id: UserId(100),
             name: "Name".into(),
-            age: 100,
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+            deleted_at: None,
+            version: Version::INITIAL,
         }];
         let repo = OnMemoryRepository {
             users: Arc::new(Mutex::new(users.clone())),
+            ..Default::default()
+        };
+
+        let res = repo
+            .get_users(&UserQuery::default(), &Pagination::default())
+            .await?;
+
+        assert_eq!(
+            res,
+            Page {
+                items: users,
+                next_cursor: None,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_users_paginated() -> anyhow::Result<()> {
+        let users = [3, 1, 2]
+            .into_iter()
+            .map(|x| User {
+                id: UserId(x),
+                name: "Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            })
+            .collect::<Vec<_>>();
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(users)),
+            ..Default::default()
+        };
+
+        let page = repo
+            .get_users(
+                &UserQuery::default(),
+                &Pagination {
+                    limit: 2,
+                    cursor: None,
+                },
+            )
+            .await?;
+
+        assert_eq!(
+            page.items.iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(page.next_cursor, page.items.last().map(Cursor::from));
+
+        let page = repo
+            .get_users(
+                &UserQuery::default(),
+                &Pagination {
+                    limit: 2,
+                    cursor: page.next_cursor,
+                },
+            )
+            .await?;
+
+        assert_eq!(
+            page.items.iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![3]
+        );
+        assert_eq!(page.next_cursor, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_users() -> anyhow::Result<()> {
+        let users = [(1, "Taro", 20), (2, "Jiro", 30), (3, "Tama", 30)]
+            .into_iter()
+            .map(|(id, name, age)| User {
+                id: UserId(id),
+                name: name.into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(2023 - age, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            })
+            .collect::<Vec<_>>();
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(users)),
+            ..Default::default()
+        };
+        let query = UserQuery {
+            sort: vec![Sort::desc(SortField::Age)],
+            ..Default::default()
+        };
+
+        let found = repo
+            .stream_users(&query)
+            .await?
+            .map_ok(|x| x.id.0)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(found, vec![2, 3, 1]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_users_filtered_and_sorted() -> anyhow::Result<()> {
+        let users = [
+            (1, "Taro", 20),
+            (2, "Jiro", 30),
+            (3, "Tanaka", 40),
+            (4, "Tama", 30),
+        ]
+        .into_iter()
+        .map(|(id, name, age)| User {
+            id: UserId(id),
+            name: name.into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(2023 - age, 1, 1),
+            deleted_at: None,
+            version: Version::INITIAL,
+        })
+        .collect::<Vec<_>>();
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(users)),
+            ..Default::default()
+        }
+        .with_clock(FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap()));
+        let query = UserQuery {
+            name_prefix: Some("Ta".into()),
+            max_age: Some(30),
+            sort: vec![Sort::desc(SortField::Age), Sort::asc(SortField::Name)],
+            ..Default::default()
         };
 
-        let res = repo.get_users().await?;
+        let page = repo
+            .get_users(
+                &query,
+                &Pagination {
+                    limit: 1,
+                    cursor: None,
+                },
+            )
+            .await?;
+
+        assert_eq!(
+            page.items.iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![4]
+        );
+
+        let page = repo
+            .get_users(
+                &query,
+                &Pagination {
+                    limit: 1,
+                    cursor: page.next_cursor,
+                },
+            )
+            .await?;
 
-        assert_eq!(res, users);
+        assert_eq!(
+            page.items.iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(page.next_cursor, None);
 
         Ok(())
     }
@@ -84,19 +500,26 @@ mod tests {
             User {
                 id: UserId(10),
                 name: "Name".into(),
-                age: 100,
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
             },
             User {
                 id: UserId(10),
                 name: "Name 2".into(),
-                age: 100,
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
             },
         ];
         let repo = OnMemoryRepository {
             users: Arc::new(Mutex::new(users.clone())),
+            ..Default::default()
         };
 
-        let res = repo.get_user(&users[0].id).await?;
+        let res = repo.get_user(&users[0].id, Deleted::Exclude).await?;
 
         assert_eq!(res, users.first().cloned());
 
@@ -108,15 +531,505 @@ mod tests {
         let repo = OnMemoryRepository::new();
 
         let user = repo
-            .create_user(NewUser {
+            .create_user(
+                NewUser {
+                    name: "Name".into(),
+                    email: Some("Name@Example.com".into()),
+                    birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await?;
+
+        assert_eq!(
+            user,
+            User {
+                id: UserId(1),
+                name: "Name".into(),
+                email: Some("name@example.com".into()),
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            }
+        );
+        assert_eq!(repo.events().await, vec![UserEvent::created(&user)]);
+
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct ConstantId;
+
+    impl IdGenerator for ConstantId {
+        fn next_id(&self) -> UserId {
+            UserId(7)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_user_if_id_taken() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository::new().with_id_generator(ConstantId);
+        let new_user = NewUser {
+            name: "Name".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+        };
+
+        repo.create_user(new_user.clone(), UserEvent::created)
+            .await?;
+        let res = repo.create_user(new_user.clone(), UserEvent::created).await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.name == "users_pkey");
+
+        let res = repo
+            .create_users(vec![new_user.clone(), new_user], UserEvent::created)
+            .await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.name == "users_pkey");
+        assert_eq!(repo.events().await.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_if_email_taken() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository::new();
+        let new_user = |email: &str| NewUser {
+            name: "Name".into(),
+            email: Some(email.into()),
+            birth_date: None,
+        };
+
+        let user = repo
+            .create_user(new_user("taro@example.com"), UserEvent::created)
+            .await?;
+        repo.delete_user(&user.id, user.version, UserEvent::deleted)
+            .await?;
+        let res = repo
+            .create_user(new_user("Taro@Example.com"), UserEvent::created)
+            .await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.field == Some("email"));
+
+        let res = repo
+            .create_users(
+                vec![new_user("jiro@example.com"), new_user("JIRO@example.com")],
+                UserEvent::created,
+            )
+            .await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.field == Some("email"));
+        assert_eq!(repo.events().await.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository::new().with_id_generator(SequentialIds::starting_at(100));
+
+        let users = repo
+            .create_users(
+                ["a", "b"]
+                    .into_iter()
+                    .map(|x| NewUser {
+                        name: x.into(),
+                        email: None,
+                        birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                    })
+                    .collect(),
+                UserEvent::created,
+            )
+            .await?;
+
+        assert_eq!(
+            users.iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![100, 101]
+        );
+        assert_eq!(
+            repo.events().await,
+            users.iter().map(UserEvent::created).collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![User {
+                id: UserId(10),
+                name: "Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            }])),
+            ..Default::default()
+        };
+
+        let res = repo
+            .update_user(
+                &UserId(10),
+                Version::INITIAL,
+                UserUpdate {
+                    name: "New Name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::updated,
+            )
+            .await?;
+
+        let expected = User {
+            id: UserId(10),
+            name: "New Name".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+            deleted_at: None,
+            version: Version(2),
+        };
+        assert_eq!(res, expected.clone());
+        assert_eq!(
+            repo.get_user(&UserId(10), Deleted::Exclude).await?,
+            Some(expected.clone())
+        );
+        assert_eq!(repo.events().await, vec![UserEvent::updated(&expected)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user_not_found() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository::new();
+
+        let res = repo
+            .update_user(
+                &UserId(10),
+                Version::INITIAL,
+                UserUpdate {
+                    name: "New Name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::updated,
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::NotFound));
+        assert_eq!(repo.events().await, vec![]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_user() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![User {
+                id: UserId(10),
+                name: "Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            }])),
+            ..Default::default()
+        };
+
+        let res = repo
+            .patch_user(
+                &UserId(10),
+                Version::INITIAL,
+                UserPatch {
+                    name: Some("New Name".into()),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await?;
+
+        assert_eq!(
+            res,
+            User {
+                id: UserId(10),
+                name: "New Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version(2),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_user_if_email_taken() -> anyhow::Result<()> {
+        let user = |id, email: &str| User {
+            id: UserId(id),
+            name: "Name".into(),
+            email: Some(email.into()),
+            birth_date: None,
+            deleted_at: None,
+            version: Version::INITIAL,
+        };
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![
+                user(10, "taro@example.com"),
+                user(11, "jiro@example.com"),
+            ])),
+            ..Default::default()
+        };
+
+        let res = repo
+            .patch_user(
+                &UserId(11),
+                Version::INITIAL,
+                UserPatch {
+                    email: Some(Some("Taro@Example.com".into())),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.field == Some("email"));
+        assert_eq!(
+            repo.get_user(&UserId(11), Deleted::Exclude).await?,
+            Some(user(11, "jiro@example.com"))
+        );
+
+        let res = repo
+            .patch_user(
+                &UserId(10),
+                Version::INITIAL,
+                UserPatch {
+                    email: Some(Some("Taro@Example.com".into())),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await?;
+        assert_eq!(res.email.as_deref(), Some("taro@example.com"));
+        assert_eq!(repo.events().await.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user_version_conflict() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![User {
+                id: UserId(10),
+                name: "Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version(2),
+            }])),
+            ..Default::default()
+        };
+
+        let res = repo
+            .patch_user(
+                &UserId(10),
+                Version::INITIAL,
+                UserPatch {
+                    birth_date: Some(NaiveDate::from_ymd_opt(2003, 1, 1)),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::VersionConflict));
+        assert_matches!(
+            repo.get_user(&UserId(10), Deleted::Exclude).await?,
+            Some(User {
+                birth_date,
+                version: Version(2),
+                ..
+            }) => {
+                assert_eq!(birth_date, NaiveDate::from_ymd_opt(1923, 1, 1));
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() -> anyhow::Result<()> {
+        let clock = FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap());
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![User {
+                id: UserId(10),
+                name: "Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            }])),
+            ..Default::default()
+        }
+        .with_clock(clock);
+
+        let res = repo
+            .delete_user(&UserId(10), Version::INITIAL, UserEvent::deleted)
+            .await?;
+
+        assert_eq!(res.deleted_at, Some(clock.now()));
+        assert_eq!(repo.get_user(&UserId(10), Deleted::Exclude).await?, None);
+        assert_eq!(
+            repo.get_users(&UserQuery::default(), &Pagination::default())
+                .await?
+                .items,
+            vec![]
+        );
+        assert_eq!(
+            repo.get_user(&UserId(10), Deleted::Include).await?,
+            Some(res.clone())
+        );
+        assert_eq!(
+            repo.get_users(
+                &UserQuery {
+                    deleted: Deleted::Include,
+                    ..Default::default()
+                },
+                &Pagination::default()
+            )
+            .await?
+            .items
+            .len(),
+            1
+        );
+        assert_matches!(
+            repo.delete_user(&UserId(10), res.version, UserEvent::deleted)
+                .await,
+            Err(DomainError::NotFound)
+        );
+        assert_matches!(
+            repo.patch_user(
+                &UserId(10),
+                res.version,
+                UserPatch::default(),
+                UserEvent::updated
+            )
+            .await,
+            Err(DomainError::NotFound)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_user() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![User {
+                id: UserId(10),
+                name: "Name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: Some(Utc::now()),
+                version: Version::INITIAL,
+            }])),
+            ..Default::default()
+        };
+
+        let res = repo
+            .restore_user(&UserId(10), Version::INITIAL, UserEvent::updated)
+            .await?;
+
+        assert_matches!(
+            res,
+            User {
+                deleted_at: None,
+                version: Version(2),
+                ..
+            }
+        );
+        assert_eq!(
+            repo.get_user(&UserId(10), Deleted::Exclude).await?,
+            Some(res)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_user() -> anyhow::Result<()> {
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(vec![User {
+                id: UserId(10),
                 name: "Name".into(),
-                age: 100,
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                deleted_at: Some(Utc::now()),
+                version: Version::INITIAL,
+            }])),
+            ..Default::default()
+        };
+
+        repo.purge_user(&UserId(10)).await?;
+        assert_matches!(
+            repo.purge_user(&UserId(10)).await,
+            Err(DomainError::NotFound)
+        );
+        assert_eq!(
+            repo.get_users(
+                &UserQuery {
+                    deleted: Deleted::Include,
+                    ..Default::default()
+                },
+                &Pagination::default()
+            )
+            .await?
+            .items,
+            vec![]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_users() -> anyhow::Result<()> {
+        let users = [
+            ("Tanaka", None),
+            ("Tanka", None),
+            ("Sato", None),
+            ("田中太郎", None),
+            ("Tanaka", Some(Utc::now())),
+        ]
+        .into_iter()
+        .zip(1..)
+        .map(|((name, deleted_at), id)| User {
+            id: UserId(id),
+            name: name.into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+            deleted_at,
+            version: Version::INITIAL,
+        })
+        .collect::<Vec<_>>();
+        let repo = OnMemoryRepository {
+            users: Arc::new(Mutex::new(users)),
+            ..Default::default()
+        };
+
+        let hits = repo.search_users(&UserSearch::new("tanaka")).await?;
+
+        assert_eq!(
+            hits.into_iter()
+                .map(|x| (x.user.id.0, x.score))
+                .collect::<Vec<_>>(),
+            vec![(1, 1.0), (2, 0.44444445)]
+        );
+
+        let hits = repo
+            .search_users(&UserSearch {
+                limit: 1,
+                ..UserSearch::new("Tanaka")
             })
             .await?;
 
-        assert_matches!(user, User { id: UserId(id), .. } => {
-            assert!(id >= 0);
-        });
+        assert_eq!(
+            hits.into_iter().map(|x| x.user.id.0).collect::<Vec<_>>(),
+            vec![1]
+        );
 
         Ok(())
     }
@@ -0,0 +1,54 @@
+//! In-process port of `pg_trgm`'s `similarity()`, so searches against [`super::OnMemoryRepository`]
+//! rank the same way as against Postgres.
+
+use std::collections::HashSet;
+
+/// Trigrams of each word, padded with two spaces in front and one behind as `pg_trgm` does.
+fn trigrams(s: &str) -> HashSet<[char; 3]> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .flat_map(|word| {
+            let chars = [' ', ' ']
+                .into_iter()
+                .chain(word.chars().flat_map(char::to_lowercase))
+                .chain([' '])
+                .collect::<Vec<_>>();
+            chars
+                .windows(3)
+                .map(|x| [x[0], x[1], x[2]])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Shared trigrams over all distinct trigrams of both strings.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let a = trigrams(a);
+    let b = trigrams(b);
+    let shared = a.intersection(&b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        return 0.0;
+    }
+    shared as f32 / total as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("word", "word", 1.0)]
+    #[case("word", "WORD", 1.0)]
+    #[case("word", "two words", 0.36363637)]
+    #[case("Tanaka", "Tanka", 0.44444445)]
+    #[case("たなか", "たなか たろう", 0.5714286)]
+    #[case("田中", "田中太郎", 0.33333334)]
+    #[case("word", "", 0.0)]
+    #[case("", "", 0.0)]
+    fn test_similarity(#[case] a: &str, #[case] b: &str, #[case] expected: f32) {
+        assert_eq!(similarity(a, b), expected);
+    }
+}
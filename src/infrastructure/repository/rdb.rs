@@ -1,19 +1,59 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection};
 
-use crate::config::CONFIG;
+use crate::{
+    config::CONFIG,
+    domain::{audit::Actor, clock::Clock, credential::TokenGenerator, id::IdGenerator},
+    infrastructure::{clock::SystemClock, token::RandomTokens},
+};
 
+pub mod api_key;
+pub mod audit;
+pub mod credential;
 pub mod entity;
+mod error;
+pub mod outbox;
+pub mod role;
 pub mod user;
 
 pub struct RdbRepository<'a, C: ConnectionTrait> {
     conn: &'a C,
+    actor: Actor,
+    ids: Option<Arc<dyn IdGenerator>>,
+    clock: Arc<dyn Clock>,
+    /// Hashes session tokens and API keys to store and find them by.
+    tokens: Arc<dyn TokenGenerator>,
 }
 
 impl<'a, C: ConnectionTrait> RdbRepository<'a, C> {
     pub fn new(conn: &'a C) -> Self {
-        Self { conn }
+        Self {
+            conn,
+            actor: Actor::system(),
+            ids: None,
+            clock: Arc::new(SystemClock),
+            tokens: Arc::new(RandomTokens),
+        }
+    }
+
+    /// Attributes the changes made through this repository to `actor` in the audit log.
+    pub fn with_actor(mut self, actor: Actor) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    /// Takes the ids of new users from `ids` instead of the `BIGSERIAL` sequence, which is then
+    /// not advanced.
+    pub fn with_id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    /// Computes ages for age range queries on the day `clock` says it is.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 }
 
@@ -31,6 +71,7 @@ pub async fn create_connection() -> anyhow::Result<DatabaseConnection> {
 #[cfg(test)]
 pub(crate) mod fixtures {
     use super::entity::users;
+    use chrono::NaiveDate;
     use sea_orm::ActiveValue;
 
     #[macro_export]
@@ -43,7 +84,7 @@ pub(crate) mod fixtures {
     pub fn user() -> users::ActiveModel {
         users::ActiveModel {
             name: ActiveValue::Set("test name".into()),
-            age: ActiveValue::Set(Some(100)),
+            birth_date: ActiveValue::Set(NaiveDate::from_ymd_opt(1923, 1, 1)),
             ..Default::default()
         }
     }
@@ -0,0 +1,273 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use validator::Validate;
+
+use crate::domain::{
+    api_key::{ApiKey, ApiKeyId, ApiKeySecret, NewApiKey, Scope},
+    credential::TokenGenerator,
+    error::{DomainError, Result},
+    repository::api_key_repository::ApiKeyRepository,
+};
+
+use super::{entity::api_keys, RdbRepository};
+
+#[async_trait::async_trait]
+impl<'a, C: ConnectionTrait + TransactionTrait> ApiKeyRepository for RdbRepository<'a, C> {
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        api_keys::Entity::find()
+            .order_by_asc(api_keys::Column::Id)
+            .all(self.conn)
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    async fn find_api_key(&self, secret: &ApiKeySecret) -> Result<Option<ApiKey>> {
+        api_keys::Entity::find()
+            .filter(api_keys::Column::KeyHash.eq(secret.hash(self.tokens.as_ref())))
+            .one(self.conn)
+            .await?
+            .map(TryInto::try_into)
+            .transpose()
+    }
+
+    async fn create_api_key(&self, key: NewApiKey, secret: &ApiKeySecret) -> Result<ApiKey> {
+        key.validate()?;
+        new_model(key, secret, self.tokens.as_ref())
+            .insert(self.conn)
+            .await?
+            .try_into()
+    }
+
+    async fn revoke_api_key(&self, id: &ApiKeyId) -> Result<ApiKey> {
+        let tx = self.conn.begin().await?;
+        let key = revoke(&tx, id).await?;
+        tx.commit().await?;
+        key.try_into()
+    }
+
+    async fn rotate_api_key(&self, id: &ApiKeyId, secret: &ApiKeySecret) -> Result<ApiKey> {
+        let tx = self.conn.begin().await?;
+        let old: ApiKey = revoke(&tx, id).await?.try_into()?;
+        let key = new_model(
+            NewApiKey {
+                owner: old.owner,
+                scopes: old.scopes,
+                expires_at: old.expires_at,
+            },
+            secret,
+            self.tokens.as_ref(),
+        )
+        .insert(&tx)
+        .await?;
+        tx.commit().await?;
+        key.try_into()
+    }
+
+    async fn touch_api_key(&self, id: &ApiKeyId, at: DateTime<Utc>) -> Result<()> {
+        api_keys::ActiveModel {
+            id: ActiveValue::Unchanged(id.0),
+            last_used_at: ActiveValue::Set(Some(at.into())),
+            ..Default::default()
+        }
+        .update(self.conn)
+        .await?;
+        Ok(())
+    }
+}
+
+fn new_model(
+    key: NewApiKey,
+    secret: &ApiKeySecret,
+    tokens: &dyn TokenGenerator,
+) -> api_keys::ActiveModel {
+    api_keys::ActiveModel {
+        prefix: ActiveValue::Set(secret.prefix().into()),
+        key_hash: ActiveValue::Set(secret.hash(tokens)),
+        owner: ActiveValue::Set(key.owner),
+        scopes: ActiveValue::Set(
+            key.scopes
+                .iter()
+                .map(Scope::as_str)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        expires_at: ActiveValue::Set(key.expires_at.map(Into::into)),
+        ..Default::default()
+    }
+}
+
+/// Marks the key revoked, unless it already is.
+async fn revoke(conn: &impl ConnectionTrait, id: &ApiKeyId) -> Result<api_keys::Model> {
+    let key: api_keys::ActiveModel = api_keys::Entity::find_by_id(id.0)
+        .filter(api_keys::Column::RevokedAt.is_null())
+        .lock_exclusive()
+        .one(conn)
+        .await?
+        .ok_or(DomainError::NotFound)?
+        .into();
+    Ok(api_keys::ActiveModel {
+        revoked_at: ActiveValue::Set(Some(Utc::now().into())),
+        ..key
+    }
+    .update(conn)
+    .await?)
+}
+
+impl TryFrom<api_keys::Model> for ApiKey {
+    type Error = DomainError;
+
+    fn try_from(x: api_keys::Model) -> Result<Self> {
+        Ok(Self {
+            id: ApiKeyId(x.id),
+            prefix: x.prefix,
+            owner: x.owner,
+            scopes: x
+                .scopes
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, String>>()
+                .map_err(|e| anyhow::anyhow!(e))?,
+            expires_at: x.expires_at.map(Into::into),
+            last_used_at: x.last_used_at.map(Into::into),
+            revoked_at: x.revoked_at.map(Into::into),
+            created_at: x.created_at.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+    use pretty_assertions::assert_eq;
+    use sea_orm::DatabaseTransaction;
+
+    use crate::infrastructure::{repository::rdb::create_connection, token::RandomTokens};
+
+    use super::*;
+
+    async fn create_transaction() -> anyhow::Result<DatabaseTransaction> {
+        create_connection()
+            .await?
+            .begin()
+            .await
+            .context("begin transaction")
+    }
+
+    fn new_key() -> NewApiKey {
+        NewApiKey {
+            owner: "batch".into(),
+            scopes: vec![Scope::UsersRead, Scope::UsersWrite],
+            expires_at: Some(Utc::now() + Duration::days(30)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_find_api_key() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let repo = RdbRepository::new(&tx);
+
+        let secret = ApiKeySecret::generate(&RandomTokens);
+        let key = repo
+            .create_api_key(new_key(), &secret)
+            .await
+            .context("create_api_key")?;
+
+        assert_matches!(&key, ApiKey { prefix, owner, scopes, last_used_at: None, revoked_at: None, .. } => {
+            assert_eq!(prefix, secret.prefix());
+            assert_eq!(owner, "batch");
+            assert_eq!(scopes, &vec![Scope::UsersRead, Scope::UsersWrite]);
+        });
+        assert_eq!(repo.find_api_key(&secret).await?, Some(key.clone()));
+        assert_eq!(
+            repo.find_api_key(&ApiKeySecret::generate(&RandomTokens))
+                .await?,
+            None
+        );
+
+        let at = Utc::now();
+        repo.touch_api_key(&key.id, at).await?;
+        assert_matches!(repo.find_api_key(&secret).await?, Some(ApiKey { last_used_at: Some(x), .. }) => {
+            assert_eq!(x.timestamp_micros(), at.timestamp_micros());
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_api_key_if_validation_error() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let repo = RdbRepository::new(&tx);
+
+        let res = repo
+            .create_api_key(
+                NewApiKey {
+                    scopes: vec![],
+                    ..new_key()
+                },
+                &ApiKeySecret::generate(&RandomTokens),
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(_)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revoke_api_key() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let repo = RdbRepository::new(&tx);
+
+        let key = repo
+            .create_api_key(new_key(), &ApiKeySecret::generate(&RandomTokens))
+            .await?;
+        let revoked = repo.revoke_api_key(&key.id).await?;
+
+        assert!(revoked.revoked_at.is_some());
+        assert_matches!(
+            repo.revoke_api_key(&key.id).await,
+            Err(DomainError::NotFound)
+        );
+        assert_matches!(
+            repo.revoke_api_key(&ApiKeyId(0)).await,
+            Err(DomainError::NotFound)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotate_api_key() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let repo = RdbRepository::new(&tx);
+
+        let old_secret = ApiKeySecret::generate(&RandomTokens);
+        let old = repo.create_api_key(new_key(), &old_secret).await?;
+        let secret = ApiKeySecret::generate(&RandomTokens);
+        let key = repo.rotate_api_key(&old.id, &secret).await?;
+
+        assert_ne!(key.id, old.id);
+        assert_eq!(key.prefix, secret.prefix());
+        assert_eq!(
+            (&key.owner, &key.scopes, key.expires_at),
+            (&old.owner, &old.scopes, old.expires_at)
+        );
+        assert_matches!(
+            repo.find_api_key(&old_secret).await?,
+            Some(ApiKey {
+                revoked_at: Some(_),
+                ..
+            })
+        );
+        assert_eq!(repo.list_api_keys().await?.last(), Some(&key));
+
+        Ok(())
+    }
+}
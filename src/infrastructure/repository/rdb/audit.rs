@@ -0,0 +1,255 @@
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use serde_json::{json, Value};
+
+use crate::domain::{
+    audit::{Actor, AuditEntry, Operation},
+    error::{DomainError, Result},
+    repository::audit_repository::AuditRepository,
+    user::{User, UserId},
+};
+
+use super::{entity::user_audit_log, RdbRepository};
+
+#[async_trait::async_trait]
+impl<'a, C: ConnectionTrait> AuditRepository for RdbRepository<'a, C> {
+    async fn get_user_history(&self, id: &UserId) -> Result<Vec<AuditEntry>> {
+        user_audit_log::Entity::find()
+            .filter(user_audit_log::Column::UserId.eq(id.0))
+            .order_by_asc(user_audit_log::Column::Id)
+            .all(self.conn)
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+}
+
+/// Writes an audit entry for a change to a user. Call it on the connection the change was made
+/// on so that both commit or roll back together.
+pub(super) async fn record(
+    conn: &impl ConnectionTrait,
+    actor: &Actor,
+    operation: Operation,
+    id: &UserId,
+    before: Option<&User>,
+    after: Option<&User>,
+) -> Result<()> {
+    user_audit_log::ActiveModel {
+        user_id: ActiveValue::Set(id.0),
+        actor: ActiveValue::Set(actor.0.clone()),
+        operation: ActiveValue::Set(operation.as_str().into()),
+        diff: ActiveValue::Set(diff(before, after)),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    Ok(())
+}
+
+/// `{"before": .., "after": ..}` with only the fields that differ, or a whole side if the other
+/// one is missing. The id is left out: the entry already has it, and clients must not see it raw.
+fn diff(before: Option<&User>, after: Option<&User>) -> Value {
+    let snapshot = |x: Option<&User>| match x.map(serde_json::to_value) {
+        Some(Ok(Value::Object(mut x))) => {
+            x.remove("id");
+            Some(x)
+        }
+        _ => None,
+    };
+    let (mut before, mut after) = (snapshot(before), snapshot(after));
+    if let (Some(b), Some(a)) = (&mut before, &mut after) {
+        let unchanged = b
+            .iter()
+            .filter(|(k, v)| a.get(*k) == Some(*v))
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+        for k in unchanged {
+            b.remove(&k);
+            a.remove(&k);
+        }
+    }
+    json!({
+        "before": before.map(Value::Object),
+        "after": after.map(Value::Object),
+    })
+}
+
+impl TryFrom<user_audit_log::Model> for AuditEntry {
+    type Error = DomainError;
+
+    fn try_from(x: user_audit_log::Model) -> Result<Self> {
+        let operation = match x.operation.as_str() {
+            "create" => Operation::Create,
+            "update" => Operation::Update,
+            "delete" => Operation::Delete,
+            "restore" => Operation::Restore,
+            "purge" => Operation::Purge,
+            s => return Err(anyhow::anyhow!("unknown audit operation: {}", s).into()),
+        };
+        Ok(Self {
+            id: x.id,
+            user_id: UserId(x.user_id),
+            actor: Actor(x.actor),
+            operation,
+            diff: x.diff,
+            created_at: x.created_at.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use sea_orm::{DatabaseTransaction, TransactionTrait};
+
+    use crate::{
+        domain::{
+            event::UserEvent,
+            repository::user_repository::UserRepository,
+            user::{NewUser, UserPatch, Version},
+        },
+        infrastructure::repository::rdb::create_connection,
+    };
+
+    use super::*;
+
+    async fn create_transaction() -> anyhow::Result<DatabaseTransaction> {
+        create_connection()
+            .await?
+            .begin()
+            .await
+            .context("begin transaction")
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = User {
+            id: UserId(1),
+            name: "name".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(2013, 1, 1),
+            deleted_at: None,
+            version: Version::INITIAL,
+        };
+        let after = User {
+            birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+            version: Version(2),
+            ..before.clone()
+        };
+
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            json!({
+                "before": {"birth_date": "2013-01-01", "version": 1},
+                "after": {"birth_date": "2003-01-01", "version": 2},
+            })
+        );
+        assert_eq!(
+            diff(None, Some(&before)),
+            json!({
+                "before": null,
+                "after": {
+                    "name": "name",
+                    "email": null,
+                    "birth_date": "2013-01-01",
+                    "deleted_at": null,
+                    "version": 1,
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_user_history() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx).with_actor(Actor("alice".into()));
+        let user = repo
+            .create_user(
+                NewUser {
+                    name: "name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2013, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await
+            .context("create_user")?;
+        let user = repo
+            .patch_user(
+                &user.id,
+                user.version,
+                UserPatch {
+                    birth_date: Some(NaiveDate::from_ymd_opt(2003, 1, 1)),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await
+            .context("patch_user")?;
+        RdbRepository::new(&tx)
+            .delete_user(&user.id, user.version, UserEvent::deleted)
+            .await
+            .context("delete_user")?;
+
+        let history = repo.get_user_history(&user.id).await?;
+
+        assert_eq!(
+            history
+                .iter()
+                .map(|x| (x.actor.0.as_str(), x.operation))
+                .collect::<Vec<_>>(),
+            vec![
+                ("alice", Operation::Create),
+                ("alice", Operation::Update),
+                ("system", Operation::Delete),
+            ]
+        );
+        assert_eq!(
+            history[1].diff["before"],
+            json!({"birth_date": "2013-01-01", "version": 1})
+        );
+        assert_eq!(
+            history[1].diff["after"],
+            json!({"birth_date": "2003-01-01", "version": 2})
+        );
+        assert!(history[2].diff["after"]["deleted_at"].is_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_rolled_back_with_failed_change() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+        let user = repo
+            .create_user(
+                NewUser {
+                    name: "name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2013, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await
+            .context("create_user")?;
+        repo.patch_user(
+            &user.id,
+            Version(5),
+            UserPatch::default(),
+            UserEvent::updated,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(repo.get_user_history(&user.id).await?.len(), 1);
+
+        Ok(())
+    }
+}
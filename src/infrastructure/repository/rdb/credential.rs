@@ -0,0 +1,303 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, JoinType,
+    QueryFilter, QuerySelect, RelationTrait, TransactionTrait,
+};
+
+use crate::domain::{
+    credential::{Credential, PasswordHash, Session, SessionToken, Sessions},
+    error::{DomainError, Result},
+    repository::credential_repository::CredentialRepository,
+    user::{normalize_email, Deleted, UserId},
+};
+
+use super::{
+    entity::{user_credentials, user_sessions, users},
+    user::filter_deleted,
+    RdbRepository,
+};
+
+#[async_trait::async_trait]
+impl<'a, C: ConnectionTrait + TransactionTrait> CredentialRepository for RdbRepository<'a, C> {
+    async fn find_credential_by_email(&self, email: &str) -> Result<Option<Credential>> {
+        let select = user_credentials::Entity::find()
+            .join(JoinType::InnerJoin, user_credentials::Relation::Users.def())
+            .filter(users::Column::Email.eq(normalize_email(email)));
+        Ok(filter_deleted(select, Deleted::Exclude)
+            .one(self.conn)
+            .await?
+            .map(Into::into))
+    }
+
+    async fn set_password_hash(
+        &self,
+        id: &UserId,
+        hash: &PasswordHash,
+        sessions: Sessions,
+    ) -> Result<()> {
+        let tx = self.conn.begin().await?;
+        filter_deleted(users::Entity::find_by_id(id.0), Deleted::Exclude)
+            .one(&tx)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        user_credentials::Entity::insert(user_credentials::ActiveModel {
+            user_id: ActiveValue::Set(id.0),
+            password_hash: ActiveValue::Set(hash.0.clone()),
+            updated_at: ActiveValue::Set(Utc::now().into()),
+        })
+        .on_conflict(
+            OnConflict::column(user_credentials::Column::UserId)
+                .update_columns([
+                    user_credentials::Column::PasswordHash,
+                    user_credentials::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(&tx)
+        .await?;
+        if sessions == Sessions::Revoke {
+            user_sessions::Entity::delete_many()
+                .filter(user_sessions::Column::UserId.eq(id.0))
+                .exec(&tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn create_session(&self, session: &Session) -> Result<()> {
+        user_sessions::Entity::insert(user_sessions::ActiveModel {
+            token_hash: ActiveValue::Set(session.token.hash(self.tokens.as_ref())),
+            user_id: ActiveValue::Set(session.user_id.0),
+            expires_at: ActiveValue::Set(session.expires_at.into()),
+            ..Default::default()
+        })
+        .exec(self.conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_session(&self, token: &SessionToken) -> Result<Option<Session>> {
+        let select = user_sessions::Entity::find_by_id(token.hash(self.tokens.as_ref()))
+            .join(JoinType::InnerJoin, user_sessions::Relation::Users.def());
+        Ok(filter_deleted(select, Deleted::Exclude)
+            .one(self.conn)
+            .await?
+            .map(|x| Session {
+                token: token.clone(),
+                user_id: UserId(x.user_id),
+                expires_at: x.expires_at.into(),
+            }))
+    }
+}
+
+impl From<user_credentials::Model> for Credential {
+    fn from(x: user_credentials::Model) -> Self {
+        Self {
+            user_id: UserId(x.user_id),
+            password_hash: PasswordHash(x.password_hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+    use pretty_assertions::assert_eq;
+    use sea_orm::{ActiveModelTrait, DatabaseTransaction};
+
+    use crate::infrastructure::{
+        repository::rdb::{create_connection, fixtures},
+        token::RandomTokens,
+    };
+
+    use super::*;
+
+    async fn create_transaction() -> anyhow::Result<DatabaseTransaction> {
+        create_connection()
+            .await?
+            .begin()
+            .await
+            .context("begin transaction")
+    }
+
+    async fn insert_user(tx: &DatabaseTransaction, email: &str) -> anyhow::Result<UserId> {
+        let user = users::ActiveModel {
+            email: ActiveValue::Set(Some(email.into())),
+            ..fixtures::user()
+        }
+        .insert(tx)
+        .await
+        .context("insert fixture")?;
+        Ok(UserId(user.id))
+    }
+
+    #[tokio::test]
+    async fn test_set_password_hash() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx, "taro@example.com").await?;
+
+        let repo = RdbRepository::new(&tx);
+        assert_eq!(
+            repo.find_credential_by_email("taro@example.com").await?,
+            None
+        );
+
+        for hash in ["$argon2id$first", "$argon2id$second"] {
+            repo.set_password_hash(&id, &PasswordHash(hash.into()), Sessions::Keep)
+                .await
+                .context("set_password_hash")?;
+        }
+
+        assert_eq!(
+            repo.find_credential_by_email("Taro@Example.com").await?,
+            Some(Credential {
+                user_id: id,
+                password_hash: PasswordHash("$argon2id$second".into()),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_password_hash_sessions() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx, "taro@example.com").await?;
+        let other = insert_user(&tx, "jiro@example.com").await?;
+
+        let repo = RdbRepository::new(&tx);
+        let session = |user_id: &UserId| Session {
+            token: SessionToken::generate(&RandomTokens),
+            user_id: user_id.clone(),
+            expires_at: Utc::now() + Duration::hours(1),
+        };
+        let (kept, revoked, others) = (session(&id), session(&id), session(&other));
+        repo.create_session(&kept).await?;
+        repo.set_password_hash(&id, &PasswordHash("$argon2id$first".into()), Sessions::Keep)
+            .await?;
+        assert!(repo.find_session(&kept.token).await?.is_some());
+
+        repo.create_session(&revoked).await?;
+        repo.create_session(&others).await?;
+        repo.set_password_hash(
+            &id,
+            &PasswordHash("$argon2id$second".into()),
+            Sessions::Revoke,
+        )
+        .await?;
+
+        assert_eq!(repo.find_session(&kept.token).await?, None);
+        assert_eq!(repo.find_session(&revoked.token).await?, None);
+        assert!(repo.find_session(&others.token).await?.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_password_hash_if_not_found() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+        let res = repo
+            .set_password_hash(
+                &UserId(0),
+                &PasswordHash("$argon2id$hash".into()),
+                Sessions::Revoke,
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::NotFound));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_credential_of_deleted_user() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx, "taro@example.com").await?;
+
+        let repo = RdbRepository::new(&tx);
+        repo.set_password_hash(&id, &PasswordHash("$argon2id$hash".into()), Sessions::Keep)
+            .await?;
+        users::ActiveModel {
+            id: ActiveValue::Unchanged(id.0),
+            deleted_at: ActiveValue::Set(Some(Utc::now().into())),
+            ..Default::default()
+        }
+        .update(&tx)
+        .await?;
+
+        assert_eq!(
+            repo.find_credential_by_email("taro@example.com").await?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_session() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx, "taro@example.com").await?;
+
+        let token = SessionToken::generate(&RandomTokens);
+        let expires_at = Utc::now() + Duration::hours(1);
+        RdbRepository::new(&tx)
+            .create_session(&Session {
+                token: token.clone(),
+                user_id: id.clone(),
+                expires_at,
+            })
+            .await
+            .context("create_session")?;
+
+        let session = user_sessions::Entity::find_by_id(token.hash(&RandomTokens))
+            .one(&tx)
+            .await?
+            .context("session not stored")?;
+        assert_eq!(session.user_id, id.0);
+        assert_eq!(session.expires_at.timestamp(), expires_at.timestamp());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_session() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx, "taro@example.com").await?;
+
+        let repo = RdbRepository::new(&tx);
+        let session = Session {
+            token: SessionToken::generate(&RandomTokens),
+            user_id: id.clone(),
+            expires_at: Utc::now() + Duration::hours(1),
+        };
+        repo.create_session(&session).await?;
+
+        let found = repo
+            .find_session(&session.token)
+            .await?
+            .context("session not found")?;
+        assert_eq!(found.user_id, id);
+        assert_eq!(found.expires_at.timestamp(), session.expires_at.timestamp());
+        assert_eq!(
+            repo.find_session(&SessionToken::generate(&RandomTokens))
+                .await?,
+            None
+        );
+
+        users::ActiveModel {
+            id: ActiveValue::Unchanged(id.0),
+            deleted_at: ActiveValue::Set(Some(Utc::now().into())),
+            ..Default::default()
+        }
+        .update(&tx)
+        .await?;
+        assert_eq!(repo.find_session(&session.token).await?, None);
+
+        Ok(())
+    }
+}
@@ -2,4 +2,10 @@
 
 pub mod prelude;
 
+pub mod api_keys;
+pub mod outbox;
+pub mod user_audit_log;
+pub mod user_credentials;
+pub mod user_roles;
+pub mod user_sessions;
 pub mod users;
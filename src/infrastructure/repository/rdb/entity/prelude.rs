@@ -1,3 +1,9 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.10.5
 
+pub use super::api_keys::Entity as ApiKeys;
+pub use super::outbox::Entity as Outbox;
+pub use super::user_audit_log::Entity as UserAuditLog;
+pub use super::user_credentials::Entity as UserCredentials;
+pub use super::user_roles::Entity as UserRoles;
+pub use super::user_sessions::Entity as UserSessions;
 pub use super::users::Entity as Users;
@@ -8,10 +8,38 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i64,
     pub name: String,
-    pub age: Option<i32>,
+    pub email: Option<String>,
+    pub birth_date: Option<Date>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub version: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_one = "super::user_credentials::Entity")]
+    UserCredentials,
+    #[sea_orm(has_many = "super::user_roles::Entity")]
+    UserRoles,
+    #[sea_orm(has_many = "super::user_sessions::Entity")]
+    UserSessions,
+}
+
+impl Related<super::user_credentials::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserCredentials.def()
+    }
+}
+
+impl Related<super::user_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRoles.def()
+    }
+}
+
+impl Related<super::user_sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserSessions.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,202 @@
+//! Translation of database errors into the domain errors callers can act on.
+
+use sea_orm::{DbErr, RuntimeErr};
+use sqlx::error::DatabaseError;
+
+use crate::domain::error::{Constraint, DomainError};
+
+/// SQLSTATE codes, see <https://www.postgresql.org/docs/current/errcodes-appendix.html>.
+mod sqlstate {
+    /// Class of errors for a lost or refused connection.
+    pub const CONNECTION_EXCEPTION: &str = "08";
+    pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+    pub const UNIQUE_VIOLATION: &str = "23505";
+    pub const CHECK_VIOLATION: &str = "23514";
+    pub const SERIALIZATION_FAILURE: &str = "40001";
+    pub const DEADLOCK_DETECTED: &str = "40P01";
+    pub const ADMIN_SHUTDOWN: &str = "57P01";
+    pub const CRASH_SHUTDOWN: &str = "57P02";
+    pub const CANNOT_CONNECT_NOW: &str = "57P03";
+}
+
+/// The fields guarded by the constraints in `schema.sql`, so clients can be told which one to fix.
+const CONSTRAINT_FIELDS: [(&str, &str); 2] = [
+    ("users_email_key", "email"),
+    ("users_birth_date_check", "birth_date"),
+];
+
+impl From<DbErr> for DomainError {
+    fn from(e: DbErr) -> Self {
+        translate(&e).unwrap_or_else(|| DomainError::Internal(e.into()))
+    }
+}
+
+/// The constraint named `name`, with its field if it guards one.
+fn constraint(name: &str) -> Constraint {
+    Constraint {
+        name: name.into(),
+        field: CONSTRAINT_FIELDS
+            .iter()
+            .find(|(x, _)| *x == name)
+            .map(|(_, field)| *field),
+    }
+}
+
+fn translate(e: &DbErr) -> Option<DomainError> {
+    let e = match e {
+        DbErr::RecordNotFound(_) => return Some(DomainError::NotFound),
+        DbErr::Conn(_) | DbErr::ConnectionAcquire => {
+            return Some(DomainError::Unavailable(e.to_string()))
+        }
+        DbErr::Exec(RuntimeErr::SqlxError(e)) | DbErr::Query(RuntimeErr::SqlxError(e)) => e,
+        _ => return None,
+    };
+    match e {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => {
+            Some(DomainError::Unavailable(e.to_string()))
+        }
+        sqlx::Error::Database(e) => translate_database_error(e.as_ref()),
+        _ => None,
+    }
+}
+
+fn translate_database_error(e: &dyn DatabaseError) -> Option<DomainError> {
+    let code = e.code()?;
+    let violated = || constraint(e.constraint().unwrap_or_default());
+    match code.as_ref() {
+        sqlstate::UNIQUE_VIOLATION => Some(DomainError::UniqueViolation(violated())),
+        sqlstate::FOREIGN_KEY_VIOLATION => Some(DomainError::ForeignKeyViolation(violated())),
+        sqlstate::CHECK_VIOLATION => Some(DomainError::CheckViolation(violated())),
+        sqlstate::SERIALIZATION_FAILURE | sqlstate::DEADLOCK_DETECTED => {
+            Some(DomainError::SerializationFailure)
+        }
+        sqlstate::ADMIN_SHUTDOWN | sqlstate::CRASH_SHUTDOWN | sqlstate::CANNOT_CONNECT_NOW => {
+            Some(DomainError::Unavailable(e.message().into()))
+        }
+        x if x.starts_with(sqlstate::CONNECTION_EXCEPTION) => {
+            Some(DomainError::Unavailable(e.message().into()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use assert_matches::assert_matches;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use sea_orm::{ConnectionTrait, DatabaseBackend, Statement, TransactionTrait};
+
+    use crate::infrastructure::repository::rdb::create_connection;
+
+    use super::*;
+
+    /// The error of a statement failing with `errcode`, optionally blaming `constraint`.
+    async fn raise(errcode: &str, constraint: Option<&str>) -> anyhow::Result<DomainError> {
+        let tx = create_connection()
+            .await?
+            .begin()
+            .await
+            .context("begin transaction")?;
+        let using = match constraint {
+            Some(x) => format!("ERRCODE = '{}', CONSTRAINT = '{}'", errcode, x),
+            None => format!("ERRCODE = '{}'", errcode),
+        };
+        let res = tx
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                format!(
+                    "DO $$ BEGIN RAISE EXCEPTION 'raised' USING {}; END $$",
+                    using
+                ),
+            ))
+            .await;
+        Ok(res.expect_err("raised").into())
+    }
+
+    #[rstest]
+    #[case("users_email_key", Some("email"))]
+    #[case("users_birth_date_check", Some("birth_date"))]
+    #[case("users_unknown_key", None)]
+    fn test_constraint(#[case] name: &str, #[case] field: Option<&'static str>) {
+        assert_eq!(
+            constraint(name),
+            Constraint {
+                name: name.into(),
+                field,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unique_violation() -> anyhow::Result<()> {
+        let e = raise("unique_violation", Some("users_email_key")).await?;
+
+        assert_matches!(e, DomainError::UniqueViolation(c) => {
+            assert_eq!(c, constraint("users_email_key"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_foreign_key_violation() -> anyhow::Result<()> {
+        let e = raise("foreign_key_violation", Some("orders_user_id_fkey")).await?;
+
+        assert_matches!(e, DomainError::ForeignKeyViolation(c) => {
+            assert_eq!(c.name, "orders_user_id_fkey");
+            assert_eq!(c.field, None);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_violation() -> anyhow::Result<()> {
+        let e = raise("check_violation", Some("users_birth_date_check")).await?;
+
+        assert_matches!(e, DomainError::CheckViolation(c) => {
+            assert_eq!(c.field, Some("birth_date"));
+        });
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("serialization_failure")]
+    #[case("deadlock_detected")]
+    #[tokio::test]
+    async fn test_serialization_failure(#[case] errcode: &str) -> anyhow::Result<()> {
+        assert_matches!(
+            raise(errcode, None).await?,
+            DomainError::SerializationFailure
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("connection_failure")]
+    #[case("admin_shutdown")]
+    #[tokio::test]
+    async fn test_connection_lost(#[case] errcode: &str) -> anyhow::Result<()> {
+        assert_matches!(raise(errcode, None).await?, DomainError::Unavailable(_));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_internal() -> anyhow::Result<()> {
+        assert_matches!(
+            raise("division_by_zero", None).await?,
+            DomainError::Internal(_)
+        );
+        assert_matches!(
+            DomainError::from(DbErr::RecordNotFound("user".into())),
+            DomainError::NotFound
+        );
+
+        Ok(())
+    }
+}
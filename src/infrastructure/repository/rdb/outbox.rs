@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Expr, LockBehavior, LockType},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+
+use crate::domain::{
+    error::Result,
+    event::{EventPublisher, UserEvent},
+};
+
+use super::entity::outbox;
+
+/// Stores an event for [`OutboxRelay`] to publish. Call it on the connection the change was made
+/// on so that the event exists if and only if the change does.
+pub(super) async fn enqueue(conn: &impl ConnectionTrait, event: &UserEvent) -> Result<()> {
+    outbox::ActiveModel {
+        event_type: ActiveValue::Set(event.name().into()),
+        payload: ActiveValue::Set(serde_json::to_value(event).map_err(anyhow::Error::from)?),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    Ok(())
+}
+
+/// Publishes stored events.
+///
+/// A single relay publishes its batch in the order the events were raised. Relays in several
+/// processes share the work, each skipping the events another is publishing, so across relays
+/// events may be published out of order, even for the same user; consumers must not rely on it.
+///
+/// Delivery is at least once: if publishing fails partway through a batch, the whole batch is
+/// published again on the next attempt. An event whose payload cannot be read is marked failed
+/// and left for an operator instead of blocking the events after it.
+pub struct OutboxRelay<'a, C: ConnectionTrait + TransactionTrait, P: EventPublisher> {
+    conn: &'a C,
+    publisher: P,
+}
+
+impl<'a, C: ConnectionTrait + TransactionTrait, P: EventPublisher> OutboxRelay<'a, C, P> {
+    pub fn new(conn: &'a C, publisher: P) -> Self {
+        Self { conn, publisher }
+    }
+
+    /// Publishes up to `limit` pending events and returns how many there were.
+    pub async fn relay(&self, limit: u64) -> Result<usize> {
+        let tx = self.conn.begin().await?;
+        let mut select = outbox::Entity::find()
+            .filter(outbox::Column::PublishedAt.is_null())
+            .filter(outbox::Column::FailedAt.is_null())
+            .order_by_asc(outbox::Column::Id)
+            .limit(limit);
+        QuerySelect::query(&mut select)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked);
+        let rows = select.all(&tx).await?;
+        let (mut published, mut failed) = (vec![], vec![]);
+        for row in &rows {
+            match serde_json::from_value(row.payload.clone()) {
+                Ok(event) => {
+                    self.publisher.publish(&event).await?;
+                    published.push(row.id);
+                }
+                Err(e) => {
+                    tracing::error!("outbox event {} has an unreadable payload: {}", row.id, e);
+                    failed.push(row.id);
+                }
+            }
+        }
+        let now = Utc::now();
+        for (column, ids) in [
+            (outbox::Column::PublishedAt, published),
+            (outbox::Column::FailedAt, failed),
+        ] {
+            outbox::Entity::update_many()
+                .col_expr(column, Expr::value(now))
+                .filter(outbox::Column::Id.is_in(ids))
+                .exec(&tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+
+    /// Relays pending events every `interval`, forever. Failures are logged and retried.
+    pub async fn run(&self, interval: Duration) {
+        loop {
+            if let Err(e) = self.relay(100).await {
+                tracing::error!("failed to relay events: {:?}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use sea_orm::DatabaseTransaction;
+
+    use crate::{
+        domain::{
+            error::DomainError,
+            event::MockEventPublisher,
+            repository::user_repository::UserRepository,
+            user::{NewUser, User},
+        },
+        infrastructure::{
+            event::memory::OnMemoryPublisher,
+            repository::rdb::{create_connection, RdbRepository},
+        },
+    };
+
+    use super::*;
+
+    async fn create_transaction() -> anyhow::Result<DatabaseTransaction> {
+        create_connection()
+            .await?
+            .begin()
+            .await
+            .context("begin transaction")
+    }
+
+    async fn pending(conn: &impl ConnectionTrait) -> anyhow::Result<Vec<UserEvent>> {
+        outbox::Entity::find()
+            .filter(outbox::Column::PublishedAt.is_null())
+            .filter(outbox::Column::FailedAt.is_null())
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|x| serde_json::from_value(x.payload).context("payload"))
+            .collect()
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_relay() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let user = RdbRepository::new(&tx)
+            .create_user(
+                NewUser {
+                    name: "Outbox".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await?;
+        let event = UserEvent::created(&user);
+        assert!(pending(&tx).await?.contains(&event));
+
+        let publisher = OnMemoryPublisher::new();
+        let relay = OutboxRelay::new(&tx, publisher.clone());
+        assert!(relay.relay(1000).await? >= 1);
+
+        assert!(publisher.events().await.contains(&event));
+        assert_eq!(pending(&tx).await?, vec![]);
+        assert_eq!(relay.relay(1000).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_relay_failed() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let user = RdbRepository::new(&tx)
+            .create_user(
+                NewUser {
+                    name: "Outbox".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await?;
+
+        let mut publisher = MockEventPublisher::new();
+        publisher
+            .expect_publish()
+            .returning(|_| Err(DomainError::Unavailable("broker down".into())));
+        let res = OutboxRelay::new(&tx, publisher).relay(1000).await;
+
+        assert!(res.is_err());
+        assert!(pending(&tx).await?.contains(&UserEvent::created(&user)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_relay_unreadable_payload() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let broken = outbox::ActiveModel {
+            event_type: ActiveValue::Set("UserCreated".into()),
+            payload: ActiveValue::Set(serde_json::json!({ "type": "UserCreated" })),
+            ..Default::default()
+        }
+        .insert(&tx)
+        .await?;
+        let user = RdbRepository::new(&tx)
+            .create_user(
+                NewUser {
+                    name: "Outbox".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await?;
+
+        let publisher = OnMemoryPublisher::new();
+        let relay = OutboxRelay::new(&tx, publisher.clone());
+        relay.relay(1000).await?;
+
+        assert!(publisher
+            .events()
+            .await
+            .contains(&UserEvent::created(&user)));
+        let broken = outbox::Entity::find_by_id(broken.id)
+            .one(&tx)
+            .await?
+            .context("outbox row")?;
+        assert!(broken.failed_at.is_some());
+        assert_eq!(broken.published_at, None);
+        assert_eq!(relay.relay(1000).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_relay_skips_locked() -> anyhow::Result<()> {
+        let conn = create_connection().await?;
+        let events = [
+            UserEvent::created(&User {
+                name: "Locked".into(),
+                ..Default::default()
+            }),
+            UserEvent::created(&User {
+                name: "Free".into(),
+                ..Default::default()
+            }),
+        ];
+        let mut ids = vec![];
+        for event in &events {
+            let row = outbox::ActiveModel {
+                event_type: ActiveValue::Set(event.name().into()),
+                payload: ActiveValue::Set(serde_json::to_value(event)?),
+                ..Default::default()
+            }
+            .insert(&conn)
+            .await?;
+            ids.push(row.id);
+        }
+
+        let res: anyhow::Result<_> = async {
+            // Another relay in the middle of publishing the first event.
+            let other = conn.begin().await?;
+            outbox::Entity::find_by_id(ids[0])
+                .lock_exclusive()
+                .one(&other)
+                .await?;
+
+            let publisher = OnMemoryPublisher::new();
+            let relay = OutboxRelay::new(&conn, publisher.clone());
+            tokio::time::timeout(Duration::from_secs(5), relay.relay(1000))
+                .await
+                .context("relay waited for the lock")??;
+            other.rollback().await?;
+            Ok(publisher.events().await)
+        }
+        .await;
+
+        outbox::Entity::delete_many()
+            .filter(outbox::Column::Id.is_in(ids))
+            .exec(&conn)
+            .await?;
+        let published = res?;
+
+        assert!(!published.contains(&events[0]));
+        assert!(published.contains(&events[1]));
+
+        Ok(())
+    }
+}
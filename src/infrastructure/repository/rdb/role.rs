@@ -0,0 +1,156 @@
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::domain::{
+    error::{DomainError, Result},
+    repository::role_repository::RoleRepository,
+    role::Role,
+    user::{Deleted, UserId},
+};
+
+use super::{
+    entity::{user_roles, users},
+    user::filter_deleted,
+    RdbRepository,
+};
+
+#[async_trait::async_trait]
+impl<'a, C: ConnectionTrait> RoleRepository for RdbRepository<'a, C> {
+    async fn find_roles(&self, id: &UserId) -> Result<Option<Vec<Role>>> {
+        if filter_deleted(users::Entity::find_by_id(id.0), Deleted::Exclude)
+            .one(self.conn)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        user_roles::Entity::find()
+            .filter(user_roles::Column::UserId.eq(id.0))
+            .order_by_asc(user_roles::Column::Role)
+            .all(self.conn)
+            .await?
+            .into_iter()
+            .map(|x| {
+                x.role
+                    .parse()
+                    .map_err(|e: String| anyhow::anyhow!(e).into())
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    async fn grant_role(&self, id: &UserId, role: Role) -> Result<()> {
+        filter_deleted(users::Entity::find_by_id(id.0), Deleted::Exclude)
+            .one(self.conn)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        user_roles::Entity::insert(user_roles::ActiveModel {
+            user_id: ActiveValue::Set(id.0),
+            role: ActiveValue::Set(role.as_str().into()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([user_roles::Column::UserId, user_roles::Column::Role])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(self.conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn revoke_role(&self, id: &UserId, role: Role) -> Result<()> {
+        let res = user_roles::Entity::delete_many()
+            .filter(user_roles::Column::UserId.eq(id.0))
+            .filter(user_roles::Column::Role.eq(role.as_str()))
+            .exec(self.conn)
+            .await?;
+        if res.rows_affected == 0 {
+            return Err(DomainError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+    use sea_orm::{ActiveModelTrait, DatabaseTransaction, TransactionTrait};
+
+    use crate::infrastructure::repository::rdb::{create_connection, fixtures};
+
+    use super::*;
+
+    async fn create_transaction() -> anyhow::Result<DatabaseTransaction> {
+        create_connection()
+            .await?
+            .begin()
+            .await
+            .context("begin transaction")
+    }
+
+    async fn insert_user(tx: &DatabaseTransaction) -> anyhow::Result<UserId> {
+        let user = fixtures::user()
+            .insert(tx)
+            .await
+            .context("insert fixture")?;
+        Ok(UserId(user.id))
+    }
+
+    #[tokio::test]
+    async fn test_grant_and_revoke_role() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+
+        let repo = RdbRepository::new(&tx);
+        assert_eq!(repo.find_roles(&id).await?, Some(vec![]));
+
+        for role in [Role::Viewer, Role::Admin, Role::Viewer] {
+            repo.grant_role(&id, role).await.context("grant_role")?;
+        }
+        assert_eq!(
+            repo.find_roles(&id).await?,
+            Some(vec![Role::Admin, Role::Viewer])
+        );
+
+        repo.revoke_role(&id, Role::Admin)
+            .await
+            .context("revoke_role")?;
+        assert_eq!(repo.find_roles(&id).await?, Some(vec![Role::Viewer]));
+        assert_matches!(
+            repo.revoke_role(&id, Role::Admin).await,
+            Err(DomainError::NotFound)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grant_role_if_not_found() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+        users::ActiveModel {
+            id: ActiveValue::Unchanged(id.0),
+            deleted_at: ActiveValue::Set(Some(Utc::now().into())),
+            ..Default::default()
+        }
+        .update(&tx)
+        .await?;
+
+        let repo = RdbRepository::new(&tx);
+        for id in [id, UserId(0)] {
+            assert_matches!(
+                repo.grant_role(&id, Role::Viewer).await,
+                Err(DomainError::NotFound)
+            );
+            assert_eq!(repo.find_roles(&id).await?, None);
+        }
+
+        Ok(())
+    }
+}
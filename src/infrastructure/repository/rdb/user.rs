@@ -1,75 +1,496 @@
-use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait};
-use validator::Validate;
+use std::{ops::Bound, pin::Pin};
+
+use chrono::NaiveDate;
+use futures::{stream::BoxStream, StreamExt};
+use sea_orm::{
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Condition, Expr, Func, SimpleExpr},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult,
+    IntoSimpleExpr, Order, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Select, StreamTrait,
+    TransactionTrait, Value,
+};
+use validator::ValidateArgs;
 
 use crate::domain::{
+    audit::Operation,
+    error::{DomainError, Result},
+    event::Raise,
+    pagination::{Cursor, Page, Pagination},
     repository::user_repository::UserRepository,
-    user::{NewUser, User, UserId},
+    user::{
+        normalize_email,
+        query::{Sort, SortField, UserQuery},
+        search::{SearchHit, UserSearch},
+        Deleted, NewUser, User, UserId, UserPatch, UserUpdate, Version,
+    },
 };
 
 use super::{
+    audit,
     entity::{self, users},
-    RdbRepository,
+    outbox, RdbRepository,
 };
 
 #[async_trait::async_trait]
-impl<'a, C: ConnectionTrait> UserRepository for RdbRepository<'a, C> {
-    async fn get_users(&self) -> anyhow::Result<Vec<User>> {
-        Ok(entity::prelude::Users::find()
+impl<'a, C: ConnectionTrait + StreamTrait + TransactionTrait> UserRepository
+    for RdbRepository<'a, C>
+{
+    async fn get_users(&self, query: &UserQuery, page: &Pagination) -> Result<Page<User>> {
+        let mut select = filter_query(entity::prelude::Users::find(), query, self.clock.today());
+        if let Some(cursor) = &page.cursor {
+            select = select.filter(after_cursor(query, cursor));
+        }
+        let users = order_query(select, query)
+            .limit(page.limit + 1)
             .all(self.conn)
             .await?
             .into_iter()
             .map(Into::into)
+            .collect();
+
+        Ok(Page::from_overfetched(users, page.limit, |x| {
+            Cursor::from(x)
+        }))
+    }
+
+    async fn stream_users<'b>(&'b self, query: &UserQuery) -> Result<BoxStream<'b, Result<User>>> {
+        let select = filter_query(entity::prelude::Users::find(), query, self.clock.today());
+        let mut rows = order_query(select, query)
+            .stream(self.conn)
+            .await?
+            .peekable();
+        // The query only runs once the stream is polled. Fail here rather than partway through
+        // whatever the caller is writing.
+        if let Some(Err(_)) = Pin::new(&mut rows).peek().await {
+            rows.next().await.transpose()?;
+        }
+        Ok(rows.map(|x| Ok(x?.into())).boxed())
+    }
+
+    async fn search_users(&self, search: &UserSearch) -> Result<Vec<SearchHit>> {
+        // `%` rather than a `similarity()` comparison so the trigram index can be used.
+        Ok(
+            filter_deleted(entity::prelude::Users::find(), Deleted::Exclude)
+                .column_as(
+                    Expr::cust_with_values(
+                        r#"similarity("users"."name", $1)"#,
+                        [search.q.as_str()],
+                    ),
+                    "score",
+                )
+                .filter(Expr::cust_with_values(
+                    r#""users"."name" % $1"#,
+                    [search.q.as_str()],
+                ))
+                .order_by_desc(Expr::cust("score"))
+                .order_by_asc(users::Column::Id)
+                .limit(search.limit)
+                .into_model::<SearchRow>()
+                .all(self.conn)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        )
+    }
+
+    async fn get_user(&self, id: &UserId, deleted: Deleted) -> Result<Option<User>> {
+        Ok(
+            filter_deleted(entity::prelude::Users::find_by_id(id.0), deleted)
+                .one(self.conn)
+                .await?
+                .map(Into::into),
+        )
+    }
+
+    async fn taken_emails(&self, emails: &[String]) -> Result<Vec<String>> {
+        if emails.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(entity::prelude::Users::find()
+            .filter(users::Column::Email.is_in(emails.iter().cloned()))
+            .all(self.conn)
+            .await?
+            .into_iter()
+            .filter_map(|x| x.email)
             .collect())
     }
 
-    async fn get_user(&self, id: &UserId) -> anyhow::Result<Option<User>> {
-        Ok(entity::prelude::Users::find_by_id(id.0)
-            .one(self.conn)
+    async fn create_user(&self, user: NewUser, raise: Raise) -> Result<User> {
+        user.validate_args(&self.clock.today())?;
+        let tx = self.conn.begin().await?;
+        let user: User = self.new_model(user).insert(&tx).await?.into();
+        audit::record(
+            &tx,
+            &self.actor,
+            Operation::Create,
+            &user.id,
+            None,
+            Some(&user),
+        )
+        .await?;
+        outbox::enqueue(&tx, &raise(&user)).await?;
+        tx.commit().await?;
+        Ok(user)
+    }
+
+    async fn create_users(&self, new_users: Vec<NewUser>, raise: Raise) -> Result<Vec<User>> {
+        if new_users.is_empty() {
+            return Ok(vec![]);
+        }
+        let today = self.clock.today();
+        for user in &new_users {
+            user.validate_args(&today)?;
+        }
+        let tx = self.conn.begin().await?;
+        let mut insert =
+            entity::prelude::Users::insert_many(new_users.into_iter().map(|x| self.new_model(x)))
+                .into_query();
+        insert.returning_all();
+        let mut created: Vec<User> =
+            users::Model::find_by_statement(tx.get_database_backend().build(&insert))
+                .all(&tx)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect();
+        // Ids are assigned in insertion order, but `RETURNING` does not promise to keep it.
+        created.sort_by_key(|x| x.id.0);
+        for user in &created {
+            audit::record(
+                &tx,
+                &self.actor,
+                Operation::Create,
+                &user.id,
+                None,
+                Some(user),
+            )
+            .await?;
+            outbox::enqueue(&tx, &raise(user)).await?;
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn update_user(
+        &self,
+        id: &UserId,
+        version: Version,
+        user: UserUpdate,
+        raise: Raise,
+    ) -> Result<User> {
+        user.validate_args(&self.clock.today())?;
+        self.update_model(
+            users::ActiveModel {
+                name: ActiveValue::Set(user.name),
+                email: ActiveValue::Set(user.email.as_deref().map(normalize_email)),
+                birth_date: ActiveValue::Set(user.birth_date),
+                ..Default::default()
+            },
+            id,
+            version,
+            Operation::Update,
+            raise,
+            Deleted::Exclude,
+        )
+        .await
+    }
+
+    async fn patch_user(
+        &self,
+        id: &UserId,
+        version: Version,
+        patch: UserPatch,
+        raise: Raise,
+    ) -> Result<User> {
+        patch.validate_args(&self.clock.today())?;
+        self.update_model(
+            users::ActiveModel {
+                name: patch
+                    .name
+                    .map(ActiveValue::Set)
+                    .unwrap_or(ActiveValue::NotSet),
+                email: patch
+                    .email
+                    .map(|x| ActiveValue::Set(x.as_deref().map(normalize_email)))
+                    .unwrap_or(ActiveValue::NotSet),
+                birth_date: patch
+                    .birth_date
+                    .map(ActiveValue::Set)
+                    .unwrap_or(ActiveValue::NotSet),
+                ..Default::default()
+            },
+            id,
+            version,
+            Operation::Update,
+            raise,
+            Deleted::Exclude,
+        )
+        .await
+    }
+
+    async fn delete_user(&self, id: &UserId, version: Version, raise: Raise) -> Result<User> {
+        self.update_model(
+            users::ActiveModel {
+                deleted_at: ActiveValue::Set(Some(self.clock.now().into())),
+                ..Default::default()
+            },
+            id,
+            version,
+            Operation::Delete,
+            raise,
+            Deleted::Exclude,
+        )
+        .await
+    }
+
+    async fn restore_user(&self, id: &UserId, version: Version, raise: Raise) -> Result<User> {
+        self.update_model(
+            users::ActiveModel {
+                deleted_at: ActiveValue::Set(None),
+                ..Default::default()
+            },
+            id,
+            version,
+            Operation::Restore,
+            raise,
+            Deleted::Include,
+        )
+        .await
+    }
+
+    async fn purge_user(&self, id: &UserId) -> Result<()> {
+        let tx = self.conn.begin().await?;
+        let before: User = entity::prelude::Users::find_by_id(id.0)
+            .lock_exclusive()
+            .one(&tx)
             .await?
-            .map(Into::into))
+            .ok_or(DomainError::NotFound)?
+            .into();
+        entity::prelude::Users::delete_by_id(id.0).exec(&tx).await?;
+        audit::record(&tx, &self.actor, Operation::Purge, id, Some(&before), None).await?;
+        tx.commit().await?;
+        Ok(())
     }
+}
 
-    async fn create_user(&self, user: NewUser) -> anyhow::Result<User> {
-        user.validate()?;
-        Ok(entity::users::ActiveModel {
-            name: sea_orm::ActiveValue::Set(user.name),
-            age: sea_orm::ActiveValue::Set(user.age.try_into().ok()),
+impl<'a, C: ConnectionTrait + TransactionTrait> RdbRepository<'a, C> {
+    fn new_model(&self, user: NewUser) -> users::ActiveModel {
+        users::ActiveModel {
+            id: self
+                .ids
+                .as_ref()
+                .map_or(ActiveValue::NotSet, |x| ActiveValue::Set(x.next_id().0)),
+            name: ActiveValue::Set(user.name),
+            email: ActiveValue::Set(user.email.as_deref().map(normalize_email)),
+            birth_date: ActiveValue::Set(user.birth_date),
             ..Default::default()
         }
-        .insert(self.conn)
-        .await?
-        .into())
+    }
+
+    /// Applies the changed columns of `model` if the row is still at `version`, bumping it, and
+    /// records the change in the audit log and the event in the outbox.
+    async fn update_model(
+        &self,
+        mut model: users::ActiveModel,
+        id: &UserId,
+        version: Version,
+        operation: Operation,
+        raise: Raise,
+        deleted: Deleted,
+    ) -> Result<User> {
+        let tx = self.conn.begin().await?;
+        let before: User = filter_deleted(entity::prelude::Users::find_by_id(id.0), deleted)
+            .lock_exclusive()
+            .one(&tx)
+            .await?
+            .ok_or(DomainError::NotFound)?
+            .into();
+        if before.version != version {
+            return Err(DomainError::VersionConflict);
+        }
+        model.id = ActiveValue::Unchanged(id.0);
+        model.version = ActiveValue::Set(version.next().0);
+        let after: User = entity::prelude::Users::update(model)
+            .exec(&tx)
+            .await?
+            .into();
+        audit::record(&tx, &self.actor, operation, id, Some(&before), Some(&after)).await?;
+        outbox::enqueue(&tx, &raise(&after)).await?;
+        tx.commit().await?;
+        Ok(after)
+    }
+}
+
+pub(super) fn filter_deleted<Q: QueryFilter>(query: Q, deleted: Deleted) -> Q {
+    match deleted {
+        Deleted::Exclude => query.filter(users::Column::DeletedAt.is_null()),
+        Deleted::Include => query,
+    }
+}
+
+fn filter_query(
+    mut select: Select<users::Entity>,
+    query: &UserQuery,
+    today: NaiveDate,
+) -> Select<users::Entity> {
+    select = filter_deleted(select, query.deleted);
+    if let Some(prefix) = &query.name_prefix {
+        let pattern = format!("{}%", escape_like(prefix));
+        select = select.filter(users::Column::Name.like(&pattern));
+    }
+    // A NULL birth date fails every comparison, so users without one match no age range.
+    let (start, end) = query.birth_date_range(today);
+    match start {
+        Bound::Included(x) => select = select.filter(users::Column::BirthDate.gte(x)),
+        Bound::Excluded(x) => select = select.filter(users::Column::BirthDate.gt(x)),
+        Bound::Unbounded => {}
+    }
+    match end {
+        Bound::Included(x) => select = select.filter(users::Column::BirthDate.lte(x)),
+        Bound::Excluded(x) => select = select.filter(users::Column::BirthDate.lt(x)),
+        Bound::Unbounded => {}
+    }
+    select
+}
+
+fn order_query(mut select: Select<users::Entity>, query: &UserQuery) -> Select<users::Entity> {
+    for key in query.sort_keys() {
+        let order = if is_descending(&key) {
+            Order::Desc
+        } else {
+            Order::Asc
+        };
+        select = select.order_by(sort_expr(key.field), order);
+    }
+    select
+}
+
+/// Rows strictly after the cursor in the query's sort order, i.e. the expanded form of
+/// `(k1, k2, ..) > (v1, v2, ..)` that also works with mixed sort directions.
+fn after_cursor(query: &UserQuery, cursor: &Cursor) -> Condition {
+    let keys = query.sort_keys();
+    keys.iter()
+        .enumerate()
+        .fold(Condition::any(), |any, (i, key)| {
+            let preceding = keys[..i].iter().fold(Condition::all(), |all, x| {
+                all.add(Expr::expr(sort_expr(x.field)).eq(cursor_value(x.field, cursor)))
+            });
+            let expr = Expr::expr(sort_expr(key.field));
+            let value = cursor_value(key.field, cursor);
+            any.add(preceding.add(if is_descending(key) {
+                expr.lt(value)
+            } else {
+                expr.gt(value)
+            }))
+        })
+}
+
+/// Whether the column of `key` is sorted in descending order. Age is sorted by birth date, which
+/// runs the other way.
+fn is_descending(key: &Sort) -> bool {
+    key.descending != (key.field == SortField::Age)
+}
+
+fn sort_expr(field: SortField) -> SimpleExpr {
+    match field {
+        SortField::Id => users::Column::Id.into_simple_expr(),
+        SortField::Name => users::Column::Name.into_simple_expr(),
+        // Unknown ages sort before any age, as `None` does in the domain, so after every birth
+        // date. A NULL would not compare against the cursor.
+        SortField::Age => Func::coalesce([
+            SimpleExpr::from(Expr::col(users::Column::BirthDate)),
+            SimpleExpr::from(Expr::val(NaiveDate::MAX)),
+        ]),
+    }
+}
+
+fn cursor_value(field: SortField, cursor: &Cursor) -> Value {
+    match field {
+        SortField::Id => cursor.id.0.into(),
+        SortField::Name => cursor.name.clone().into(),
+        SortField::Age => cursor.birth_date.unwrap_or(NaiveDate::MAX).into(),
     }
 }
 
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 impl From<users::Model> for User {
     fn from(x: users::Model) -> Self {
         Self {
             id: UserId(x.id),
             name: x.name,
-            age: x.age.and_then(|x| x.try_into().ok()).unwrap_or_default(),
+            email: x.email,
+            birth_date: x.birth_date,
+            deleted_at: x.deleted_at.map(Into::into),
+            version: Version(x.version),
+        }
+    }
+}
+
+#[derive(FromQueryResult)]
+struct SearchRow {
+    id: i64,
+    name: String,
+    email: Option<String>,
+    birth_date: Option<NaiveDate>,
+    deleted_at: Option<DateTimeWithTimeZone>,
+    version: i64,
+    score: f32,
+}
+
+impl From<SearchRow> for SearchHit {
+    fn from(x: SearchRow) -> Self {
+        Self {
+            user: users::Model {
+                id: x.id,
+                name: x.name,
+                email: x.email,
+                birth_date: x.birth_date,
+                deleted_at: x.deleted_at,
+                version: x.version,
+            }
+            .into(),
+            score: x.score,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use anyhow::Context;
     use assert_matches::assert_matches;
+    use chrono::Utc;
+    use futures::TryStreamExt;
     use pretty_assertions::assert_eq;
     use sea_orm::{ActiveModelTrait, DatabaseTransaction, TransactionTrait};
     use validator::ValidationErrors;
 
-    use crate::infrastructure::repository::rdb::{create_connection, entity};
+    use crate::{
+        domain::{
+            clock::{Clock, FixedClock},
+            event::UserEvent,
+        },
+        infrastructure::{
+            id::sequential::SequentialIds,
+            repository::rdb::{create_connection, entity},
+        },
+    };
 
     use super::*;
 
     async fn create_transaction() -> anyhow::Result<DatabaseTransaction> {
-        Ok(create_connection()
+        create_connection()
             .await?
             .begin()
             .await
-            .context("begin transaction")?)
+            .context("begin transaction")
     }
 
     #[tokio::test]
@@ -78,7 +499,7 @@ mod tests {
 
         entity::users::ActiveModel {
             name: sea_orm::ActiveValue::Set("name".into()),
-            age: sea_orm::ActiveValue::Set(Some(100)),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(1923, 1, 1)),
             ..Default::default()
         }
         .save(&tx)
@@ -87,14 +508,18 @@ mod tests {
 
         let repo = RdbRepository::new(&tx);
 
-        let users = repo.get_users().await.context("get_users")?;
+        let users = repo
+            .get_users(&UserQuery::default(), &Pagination::default())
+            .await
+            .context("get_users")?;
 
-        assert_matches!(&users[..], [user] => {
+        assert_eq!(users.next_cursor, None);
+        assert_matches!(&users.items[..], [user] => {
             assert_matches!(user.id, UserId(x) => {
                 assert!(x > 0);
             });
             assert_eq!(user.name, "name");
-            assert_eq!(user.age, 100);
+            assert_eq!(user.birth_date, NaiveDate::from_ymd_opt(1923, 1, 1));
         });
 
         Ok(())
@@ -106,7 +531,7 @@ mod tests {
 
         let mut user = entity::users::ActiveModel {
             name: sea_orm::ActiveValue::Set("name".into()),
-            age: sea_orm::ActiveValue::Set(Some(100)),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(1923, 1, 1)),
             ..Default::default()
         }
         .save(&tx)
@@ -116,14 +541,14 @@ mod tests {
         let repo = RdbRepository::new(&tx);
 
         let user = repo
-            .get_user(&UserId(user.id.take().unwrap()))
+            .get_user(&UserId(user.id.take().unwrap()), Deleted::Exclude)
             .await
             .context("get_user")?;
 
         assert_matches!(user, Some(user) => {
             assert_matches!(user.id, UserId(x) if x > 0);
             assert_eq!(user.name, "name");
-            assert_eq!(user.age, 100);
+            assert_eq!(user.birth_date, NaiveDate::from_ymd_opt(1923, 1, 1));
         });
 
         Ok(())
@@ -136,17 +561,21 @@ mod tests {
         let repo = RdbRepository::new(&tx);
 
         let user = repo
-            .create_user(NewUser {
-                name: "name".into(),
-                age: 100,
-            })
+            .create_user(
+                NewUser {
+                    name: "name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                },
+                UserEvent::created,
+            )
             .await
             .context("create_user")?;
 
-        assert_matches!(user, User { id: UserId(id), name, age} => {
+        assert_matches!(user, User { id: UserId(id), name, email: None, birth_date, deleted_at: None, version: Version::INITIAL } => {
             assert!(id > 0);
             assert_eq!(name, "name");
-            assert_eq!(age, 100);
+            assert_eq!(birth_date, NaiveDate::from_ymd_opt(1923, 1, 1));
         });
 
         Ok(())
@@ -159,19 +588,760 @@ mod tests {
         let repo = RdbRepository::new(&tx);
 
         let res = repo
-            .create_user(NewUser {
-                name: "".into(),
-                age: 100,
-            })
+            .create_user(
+                NewUser {
+                    name: "".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                },
+                UserEvent::created,
+            )
             .await;
 
-        assert_matches!(res, Err(e) => {
-            match e.downcast::<ValidationErrors>() {
-                Ok(e) => assert!(ValidationErrors::has_error(&Err(e), "name")),
-                Err(e) => panic!("Not ValidationErrors: {:?}", e),
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_if_email_taken() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+        let new_user = |email: &str| NewUser {
+            name: "name".into(),
+            email: Some(email.into()),
+            birth_date: None,
+        };
+
+        let user = repo
+            .create_user(new_user("Taro@Example.com"), UserEvent::created)
+            .await
+            .context("create_user")?;
+        assert_eq!(user.email.as_deref(), Some("taro@example.com"));
+
+        let res = repo
+            .create_user(new_user("taro@example.COM"), UserEvent::created)
+            .await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.name == "users_email_key");
+
+        let other = repo
+            .create_user(new_user("jiro@example.com"), UserEvent::created)
+            .await
+            .context("create_user")?;
+        let res = repo
+            .patch_user(
+                &other.id,
+                other.version,
+                UserPatch {
+                    email: Some(Some("TARO@example.com".into())),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await;
+        assert_matches!(res, Err(DomainError::UniqueViolation(c)) if c.name == "users_email_key");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_taken_emails() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+        let user = repo
+            .create_user(
+                NewUser {
+                    name: "name".into(),
+                    email: Some("Taken@example.com".into()),
+                    birth_date: None,
+                },
+                UserEvent::created,
+            )
+            .await?;
+        repo.delete_user(&user.id, user.version, UserEvent::deleted)
+            .await?;
+
+        let taken = repo
+            .taken_emails(&["taken@example.com".into(), "free@example.com".into()])
+            .await?;
+
+        assert_eq!(taken, vec!["taken@example.com".to_string()]);
+        assert_eq!(repo.taken_emails(&[]).await?, Vec::<String>::new());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_birth_date_check_constraint() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let res = entity::users::ActiveModel {
+            name: sea_orm::ActiveValue::Set("name".into()),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(1800, 1, 1)),
+            ..Default::default()
+        }
+        .insert(&tx)
+        .await
+        .map_err(DomainError::from);
+
+        assert_matches!(res, Err(DomainError::CheckViolation(c)) => {
+            assert_eq!(c.name, "users_birth_date_check");
+            assert_eq!(c.field, Some("birth_date"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+
+        let users = repo
+            .create_users(
+                (1..=3)
+                    .map(|x| NewUser {
+                        name: format!("name{}", x),
+                        email: None,
+                        birth_date: NaiveDate::from_ymd_opt(2000 + x, 1, 1),
+                    })
+                    .collect(),
+                UserEvent::created,
+            )
+            .await
+            .context("create_users")?;
+
+        assert_eq!(
+            users
+                .iter()
+                .map(|x| (x.name.as_str(), x.birth_date))
+                .collect::<Vec<_>>(),
+            vec![
+                ("name1", NaiveDate::from_ymd_opt(2001, 1, 1)),
+                ("name2", NaiveDate::from_ymd_opt(2002, 1, 1)),
+                ("name3", NaiveDate::from_ymd_opt(2003, 1, 1))
+            ]
+        );
+        for user in users {
+            assert_eq!(repo.get_user(&user.id, Deleted::Exclude).await?, Some(user));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_with_id_generator() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx)
+            .with_id_generator(Arc::new(SequentialIds::starting_at(1 << 40)));
+
+        let user = repo
+            .create_user(
+                NewUser {
+                    name: "name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                },
+                UserEvent::created,
+            )
+            .await
+            .context("create_user")?;
+        let users = repo
+            .create_users(
+                vec![
+                    NewUser {
+                        name: "name".into(),
+                        email: None,
+                        birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                    };
+                    2
+                ],
+                UserEvent::created,
+            )
+            .await
+            .context("create_users")?;
+
+        assert_eq!(user.id, UserId(1 << 40));
+        assert_eq!(
+            users.into_iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![(1 << 40) + 1, (1 << 40) + 2]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_if_validation_error() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+
+        let res = repo
+            .create_users(
+                vec![
+                    NewUser {
+                        name: "name".into(),
+                        email: None,
+                        birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                    },
+                    NewUser {
+                        name: "".into(),
+                        email: None,
+                        birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                    },
+                ],
+                UserEvent::created,
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(_)));
+        assert_eq!(
+            repo.get_users(
+                &UserQuery {
+                    name_prefix: Some("name".into()),
+                    ..Default::default()
+                },
+                &Pagination::default()
+            )
+            .await?
+            .items
+            .into_iter()
+            .filter(|x| x.name == "name" && x.birth_date == NaiveDate::from_ymd_opt(1923, 1, 1))
+            .count(),
+            0
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let mut user = entity::users::ActiveModel {
+            name: sea_orm::ActiveValue::Set("name".into()),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(1923, 1, 1)),
+            ..Default::default()
+        }
+        .save(&tx)
+        .await
+        .context("insert fixture")?;
+        let id = UserId(user.id.take().unwrap());
+
+        let repo = RdbRepository::new(&tx);
+
+        let res = repo
+            .update_user(
+                &id,
+                Version::INITIAL,
+                UserUpdate {
+                    name: "new name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::updated,
+            )
+            .await
+            .context("update_user")?;
+
+        assert_eq!(
+            res,
+            User {
+                id: id.clone(),
+                name: "new name".into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                deleted_at: None,
+                version: Version(2),
             }
+        );
+        assert_eq!(repo.get_user(&id, Deleted::Exclude).await?, Some(res));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user_not_found() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+
+        let res = repo
+            .update_user(
+                &UserId(0),
+                Version::INITIAL,
+                UserUpdate {
+                    name: "new name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::updated,
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::NotFound));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(UserPatch { name: Some("new name".into()), email: None, birth_date: None }, "new name", NaiveDate::from_ymd_opt(1923, 1, 1))]
+    #[case(UserPatch { name: None, email: None, birth_date: Some(NaiveDate::from_ymd_opt(2003, 1, 1)) }, "name", NaiveDate::from_ymd_opt(2003, 1, 1))]
+    #[case(UserPatch { name: None, email: None, birth_date: Some(None) }, "name", None)]
+    #[case(UserPatch::default(), "name", NaiveDate::from_ymd_opt(1923, 1, 1))]
+    #[tokio::test]
+    async fn test_patch_user(
+        #[case] patch: UserPatch,
+        #[case] name: &str,
+        #[case] birth_date: Option<NaiveDate>,
+    ) -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let mut user = entity::users::ActiveModel {
+            name: sea_orm::ActiveValue::Set("name".into()),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(1923, 1, 1)),
+            ..Default::default()
+        }
+        .save(&tx)
+        .await
+        .context("insert fixture")?;
+        let id = UserId(user.id.take().unwrap());
+
+        let repo = RdbRepository::new(&tx);
+
+        let res = repo
+            .patch_user(&id, Version::INITIAL, patch, UserEvent::updated)
+            .await
+            .context("patch_user")?;
+
+        assert_eq!(res.id, id);
+        assert_eq!(res.name, name);
+        assert_eq!(res.birth_date, birth_date);
+        assert_eq!(res.version, Version(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_user_if_validation_error() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+
+        let repo = RdbRepository::new(&tx);
+
+        let res = repo
+            .patch_user(
+                &UserId(0),
+                Version::INITIAL,
+                UserPatch {
+                    name: Some("".into()),
+                    ..Default::default()
+                },
+                UserEvent::updated,
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name"));
         });
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_update_user_version_conflict() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+
+        let repo = RdbRepository::new(&tx);
+        let update = UserUpdate {
+            name: "new name".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+        };
+
+        repo.update_user(&id, Version::INITIAL, update.clone(), UserEvent::updated)
+            .await
+            .context("update_user")?;
+
+        assert_matches!(
+            repo.update_user(&id, Version::INITIAL, update.clone(), UserEvent::updated)
+                .await,
+            Err(DomainError::VersionConflict)
+        );
+        assert_matches!(
+            repo.delete_user(&id, Version::INITIAL, UserEvent::deleted)
+                .await,
+            Err(DomainError::VersionConflict)
+        );
+        assert_matches!(
+            repo.update_user(&UserId(0), Version::INITIAL, update, UserEvent::updated)
+                .await,
+            Err(DomainError::NotFound)
+        );
+
+        Ok(())
+    }
+
+    async fn insert_user(tx: &DatabaseTransaction) -> anyhow::Result<UserId> {
+        let mut user = entity::users::ActiveModel {
+            name: sea_orm::ActiveValue::Set("name".into()),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(1923, 1, 1)),
+            ..Default::default()
+        }
+        .save(tx)
+        .await
+        .context("insert fixture")?;
+        Ok(UserId(user.id.take().unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+
+        let clock = FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap());
+        let repo = RdbRepository::new(&tx).with_clock(Arc::new(clock));
+
+        let res = repo
+            .delete_user(&id, Version::INITIAL, UserEvent::deleted)
+            .await
+            .context("delete_user")?;
+
+        assert_eq!(res.deleted_at, Some(clock.now()));
+        assert_eq!(repo.get_user(&id, Deleted::Exclude).await?, None);
+        assert_eq!(
+            repo.get_user(&id, Deleted::Include).await?,
+            Some(res.clone())
+        );
+        assert_matches!(
+            repo.delete_user(&id, res.version, UserEvent::deleted).await,
+            Err(DomainError::NotFound)
+        );
+        assert_matches!(
+            repo.update_user(
+                &id,
+                res.version,
+                UserUpdate {
+                    name: "new name".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                },
+                UserEvent::updated
+            )
+            .await,
+            Err(DomainError::NotFound)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_users_with_deleted() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+
+        let repo = RdbRepository::new(&tx);
+        repo.delete_user(&id, Version::INITIAL, UserEvent::deleted)
+            .await
+            .context("delete_user")?;
+
+        assert_eq!(
+            repo.get_users(&UserQuery::default(), &Pagination::default())
+                .await?
+                .items,
+            vec![]
+        );
+        assert_matches!(
+            &repo
+                .get_users(
+                    &UserQuery {
+                        deleted: Deleted::Include,
+                        ..Default::default()
+                    },
+                    &Pagination::default()
+                )
+                .await?
+                .items[..],
+            [user] => {
+                assert_eq!(user.id, id);
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_users_paginated() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let mut ids = vec![];
+        for _ in 0..3 {
+            ids.push(insert_user(&tx).await?);
+        }
+
+        let repo = RdbRepository::new(&tx);
+
+        let page = repo
+            .get_users(
+                &UserQuery::default(),
+                &Pagination {
+                    limit: 2,
+                    cursor: None,
+                },
+            )
+            .await
+            .context("get_users")?;
+
+        assert_eq!(
+            page.items.into_iter().map(|x| x.id).collect::<Vec<_>>(),
+            ids[..2]
+        );
+        assert_eq!(
+            page.next_cursor.as_ref().map(|x| x.id.clone()),
+            Some(ids[1].clone())
+        );
+
+        let page = repo
+            .get_users(
+                &UserQuery::default(),
+                &Pagination {
+                    limit: 2,
+                    cursor: page.next_cursor,
+                },
+            )
+            .await
+            .context("get_users")?;
+
+        assert_eq!(
+            page.items.into_iter().map(|x| x.id).collect::<Vec<_>>(),
+            ids[2..]
+        );
+        assert_eq!(page.next_cursor, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_user() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+
+        let repo = RdbRepository::new(&tx);
+        repo.delete_user(&id, Version::INITIAL, UserEvent::deleted)
+            .await
+            .context("delete_user")?;
+
+        let res = repo
+            .restore_user(&id, Version(2), UserEvent::updated)
+            .await
+            .context("restore_user")?;
+
+        assert_matches!(
+            res,
+            User {
+                deleted_at: None,
+                version: Version(3),
+                ..
+            }
+        );
+        assert_eq!(repo.get_user(&id, Deleted::Exclude).await?, Some(res));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_user() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let id = insert_user(&tx).await?;
+
+        let repo = RdbRepository::new(&tx);
+
+        repo.purge_user(&id).await.context("purge_user")?;
+        assert_matches!(repo.purge_user(&id).await, Err(DomainError::NotFound));
+        assert_eq!(repo.get_user(&id, Deleted::Include).await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_users() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let mut ids = vec![];
+        for (name, age) in [("Taro", 20), ("Jiro", 30), ("Tanaka", 40), ("Tama", 30)] {
+            let mut user = entity::users::ActiveModel {
+                name: sea_orm::ActiveValue::Set(name.into()),
+                birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(2023 - age, 1, 1)),
+                ..Default::default()
+            }
+            .save(&tx)
+            .await
+            .context("insert fixture")?;
+            ids.push(user.id.take().unwrap());
+        }
+
+        let repo = RdbRepository::new(&tx).with_clock(Arc::new(FixedClock(
+            NaiveDate::from_ymd_opt(2023, 6, 15).unwrap(),
+        )));
+        let query = UserQuery {
+            name_prefix: Some("Ta".into()),
+            max_age: Some(30),
+            sort: vec![Sort::desc(SortField::Age), Sort::asc(SortField::Name)],
+            ..Default::default()
+        };
+
+        let found = repo
+            .stream_users(&query)
+            .await
+            .context("stream_users")?
+            .map_ok(|x| x.id.0)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(found, vec![ids[3], ids[0]]);
+
+        Ok(())
+    }
+
+    /// Ids of every user matching `query`, fetched one page of one at a time.
+    async fn all_pages(
+        repo: &RdbRepository<'_, DatabaseTransaction>,
+        query: &UserQuery,
+    ) -> anyhow::Result<Vec<i64>> {
+        let mut found = vec![];
+        let mut cursor = None;
+        loop {
+            let page = repo
+                .get_users(query, &Pagination { limit: 1, cursor })
+                .await
+                .context("get_users")?;
+            found.extend(page.items.into_iter().map(|x| x.id.0));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                return Ok(found);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_users_filtered_and_sorted() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let mut ids = vec![];
+        for (name, age) in [
+            ("Taro", Some(20)),
+            ("Jiro", Some(30)),
+            ("Tanaka", Some(40)),
+            ("Tama", Some(30)),
+            ("Ta%", None),
+        ] {
+            let mut user = entity::users::ActiveModel {
+                name: sea_orm::ActiveValue::Set(name.into()),
+                birth_date: sea_orm::ActiveValue::Set(
+                    age.and_then(|x| NaiveDate::from_ymd_opt(2023 - x, 1, 1)),
+                ),
+                ..Default::default()
+            }
+            .save(&tx)
+            .await
+            .context("insert fixture")?;
+            ids.push(user.id.take().unwrap());
+        }
+
+        let repo = RdbRepository::new(&tx).with_clock(Arc::new(FixedClock(
+            NaiveDate::from_ymd_opt(2023, 6, 15).unwrap(),
+        )));
+        let query = UserQuery {
+            name_prefix: Some("Ta".into()),
+            max_age: Some(30),
+            sort: vec![Sort::desc(SortField::Age), Sort::asc(SortField::Name)],
+            ..Default::default()
+        };
+
+        // Users without an age match no age range, but sort before any age.
+        assert_eq!(all_pages(&repo, &query).await?, vec![ids[3], ids[0]]);
+        assert_eq!(
+            all_pages(
+                &repo,
+                &UserQuery {
+                    max_age: None,
+                    ..query
+                }
+            )
+            .await?,
+            vec![ids[2], ids[3], ids[0], ids[4]]
+        );
+
+        let page = repo
+            .get_users(
+                &UserQuery {
+                    name_prefix: Some("Ta%".into()),
+                    ..Default::default()
+                },
+                &Pagination::default(),
+            )
+            .await
+            .context("get_users")?;
+
+        assert_eq!(
+            page.items.into_iter().map(|x| x.id.0).collect::<Vec<_>>(),
+            vec![ids[4]]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_users() -> anyhow::Result<()> {
+        let tx = create_transaction().await?;
+        let mut ids = vec![];
+        for (name, deleted_at) in [
+            ("Tanaka", None),
+            ("Tanka", None),
+            ("Sato", None),
+            ("田中太郎", None),
+            ("Tanaka", Some(Utc::now().into())),
+        ] {
+            let mut user = entity::users::ActiveModel {
+                name: sea_orm::ActiveValue::Set(name.into()),
+                deleted_at: sea_orm::ActiveValue::Set(deleted_at),
+                ..Default::default()
+            }
+            .save(&tx)
+            .await
+            .context("insert fixture")?;
+            ids.push(user.id.take().unwrap());
+        }
+
+        let repo = RdbRepository::new(&tx);
+
+        let hits = repo
+            .search_users(&UserSearch::new("tanaka"))
+            .await
+            .context("search_users")?;
+
+        assert_eq!(
+            hits.into_iter()
+                .map(|x| (x.user.id.0, x.score))
+                .collect::<Vec<_>>(),
+            vec![(ids[0], 1.0), (ids[1], 0.44444445)]
+        );
+
+        let hits = repo
+            .search_users(&UserSearch::new("田中"))
+            .await
+            .context("search_users")?;
+
+        assert_eq!(
+            hits.into_iter()
+                .map(|x| (x.user.id.0, x.score))
+                .collect::<Vec<_>>(),
+            vec![(ids[3], 0.33333334)]
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,47 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::domain::credential::TokenGenerator;
+
+/// 256 bits from the operating system's CSPRNG, base64url encoded, stored as hex encoded SHA-256.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomTokens;
+
+impl TokenGenerator for RandomTokens {
+    fn random_token(&self) -> String {
+        let mut bytes = [0; 32];
+        OsRng.fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn hash_token(&self, token: &str) -> String {
+        Sha256::digest(token.as_bytes())
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_random_token() {
+        let token = RandomTokens.random_token();
+
+        assert_eq!(token.len(), 43);
+        assert_ne!(token, RandomTokens.random_token());
+    }
+
+    #[test]
+    fn test_hash_token() {
+        assert_eq!(
+            RandomTokens.hash_token("token"),
+            "3c469e9d6c5875d37a43f353d4f88e61fcf812c66eee3457465a40b0da4153e0"
+        );
+    }
+}
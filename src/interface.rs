@@ -1 +1,5 @@
 pub mod controller;
+pub mod cursor;
+pub mod export;
+pub mod presenter;
+pub mod public_id;
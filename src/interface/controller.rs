@@ -1 +1,3 @@
+pub mod api_keys;
+pub mod auth;
 pub mod users;
@@ -0,0 +1,43 @@
+use crate::{
+    domain::{
+        api_key::{ApiKey, ApiKeyId, ApiKeySecret, NewApiKey},
+        credential::TokenGenerator,
+        error::Result,
+        repository::api_key_repository::ApiKeyRepository,
+    },
+    usecase::api_key::{
+        authenticate::AuthenticateApiKey, create::CreateApiKey, list::ListApiKeys,
+        revoke::RevokeApiKey,
+    },
+};
+
+pub async fn authenticate_api_key(
+    repo: &impl ApiKeyRepository,
+    secret: &ApiKeySecret,
+) -> Result<ApiKey> {
+    AuthenticateApiKey::new(repo).run(secret).await
+}
+
+pub async fn list_api_keys(repo: &impl ApiKeyRepository) -> Result<Vec<ApiKey>> {
+    ListApiKeys::new(repo).run().await
+}
+
+pub async fn create_api_key(
+    repo: &impl ApiKeyRepository,
+    tokens: &dyn TokenGenerator,
+    key: NewApiKey,
+) -> Result<(ApiKey, ApiKeySecret)> {
+    CreateApiKey::new(repo, tokens).run(key).await
+}
+
+pub async fn rotate_api_key(
+    repo: &impl ApiKeyRepository,
+    tokens: &dyn TokenGenerator,
+    id: &ApiKeyId,
+) -> Result<(ApiKey, ApiKeySecret)> {
+    CreateApiKey::new(repo, tokens).rotate(id).await
+}
+
+pub async fn revoke_api_key(repo: &impl ApiKeyRepository, id: &ApiKeyId) -> Result<ApiKey> {
+    RevokeApiKey::new(repo).run(id).await
+}
@@ -0,0 +1,57 @@
+use chrono::Duration;
+
+use crate::{
+    domain::{
+        credential::{
+            Credentials, NewPassword, PasswordHasher, PasswordPolicy, Session, SessionToken,
+            TokenGenerator,
+        },
+        error::Result,
+        repository::{
+            credential_repository::CredentialRepository, role_repository::RoleRepository,
+        },
+        role::Role,
+        user::UserId,
+    },
+    usecase::{
+        auth::{
+            login::Login, roles::GetRoles, session::AuthenticateSession, set_password::SetPassword,
+        },
+        policy::{Authorizer, Subject},
+    },
+};
+
+pub async fn login(
+    repo: &impl CredentialRepository,
+    hasher: &dyn PasswordHasher,
+    tokens: &dyn TokenGenerator,
+    ttl: Duration,
+    credentials: Credentials,
+) -> Result<Session> {
+    Login::new(repo, hasher, tokens, ttl).run(credentials).await
+}
+
+pub async fn authenticate_session(
+    repo: &impl CredentialRepository,
+    token: &SessionToken,
+) -> Result<Session> {
+    AuthenticateSession::new(repo).run(token).await
+}
+
+pub async fn get_roles(repo: &impl RoleRepository, id: &UserId) -> Result<Vec<Role>> {
+    GetRoles::new(repo).run(id).await
+}
+
+pub async fn set_password(
+    repo: &impl CredentialRepository,
+    hasher: &dyn PasswordHasher,
+    policy: &PasswordPolicy,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    id: &UserId,
+    password: NewPassword,
+) -> Result<()> {
+    SetPassword::new(repo, hasher, policy, auth)
+        .run(subject, id, password)
+        .await
+}
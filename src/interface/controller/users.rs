@@ -1,5 +1,145 @@
-use crate::domain::{repository::user_repository::UserRepository, user::User};
+use futures::{stream::BoxStream, Stream};
 
-pub async fn get_users(repo: &impl UserRepository) -> anyhow::Result<Vec<User>> {
-    repo.get_users().await
+use crate::{
+    domain::{
+        audit::AuditEntry,
+        clock::Clock,
+        error::Result,
+        pagination::{Page, Pagination},
+        repository::{audit_repository::AuditRepository, user_repository::UserRepository},
+        user::{
+            import::{ImportReport, ImportRow},
+            query::UserQuery,
+            search::{SearchHit, UserSearch},
+            Deleted, NewUser, User, UserId, UserPatch, UserUpdate, Version,
+        },
+    },
+    usecase::{
+        policy::{Authorizer, Subject},
+        user::{
+            create::CreateUser, delete::DeleteUser, export::ExportUsers, get::GetUser,
+            history::GetUserHistory, import::ImportUsers, list::ListUsers, search::SearchUsers,
+            update::UpdateUser,
+        },
+    },
+};
+
+pub async fn get_users(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    query: &UserQuery,
+    page: &Pagination,
+) -> Result<Page<User>> {
+    ListUsers::new(repo, auth).run(subject, query, page).await
+}
+
+pub async fn export_users<'a>(
+    repo: &'a impl UserRepository,
+    auth: &'a dyn Authorizer,
+    subject: &Subject,
+    query: &UserQuery,
+) -> Result<BoxStream<'a, Result<User>>> {
+    ExportUsers::new(repo, auth).run(subject, query).await
+}
+
+pub async fn search_users(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    search: &UserSearch,
+) -> Result<Vec<SearchHit>> {
+    SearchUsers::new(repo, auth).run(subject, search).await
+}
+
+pub async fn get_user(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    id: &UserId,
+    deleted: Deleted,
+) -> Result<User> {
+    GetUser::new(repo, auth).run(subject, id, deleted).await
+}
+
+pub async fn create_user(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    clock: &dyn Clock,
+    subject: &Subject,
+    user: NewUser,
+) -> Result<User> {
+    CreateUser::new(repo, auth, clock).run(subject, user).await
+}
+
+pub async fn import_users(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    clock: &dyn Clock,
+    subject: &Subject,
+    rows: impl Stream<Item = ImportRow> + Send,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    ImportUsers::new(repo, auth, clock)
+        .run(subject, rows, dry_run)
+        .await
+}
+
+pub async fn update_user(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    clock: &dyn Clock,
+    subject: &Subject,
+    id: &UserId,
+    version: Version,
+    user: UserUpdate,
+) -> Result<User> {
+    UpdateUser::new(repo, auth, clock)
+        .run(subject, id, version, user)
+        .await
+}
+
+pub async fn patch_user(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    clock: &dyn Clock,
+    subject: &Subject,
+    id: &UserId,
+    version: Version,
+    patch: UserPatch,
+) -> Result<User> {
+    UpdateUser::new(repo, auth, clock)
+        .patch(subject, id, version, patch)
+        .await
+}
+
+pub async fn delete_user(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    id: &UserId,
+    version: Version,
+) -> Result<User> {
+    DeleteUser::new(repo, auth).run(subject, id, version).await
+}
+
+pub async fn restore_user(
+    repo: &impl UserRepository,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    id: &UserId,
+    version: Version,
+) -> Result<User> {
+    DeleteUser::new(repo, auth)
+        .restore(subject, id, version)
+        .await
+}
+
+pub async fn get_user_history(
+    repo: &impl AuditRepository,
+    auth: &dyn Authorizer,
+    subject: &Subject,
+    id: &UserId,
+) -> Result<Vec<AuditEntry>> {
+    GetUserHistory::new(repo, auth).run(subject, id).await
 }
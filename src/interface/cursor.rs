@@ -0,0 +1,98 @@
+//! Page cursors as clients see them: opaque tokens that hold the public id rather than the id.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::domain::{error::DomainError, pagination::Cursor};
+
+use super::public_id::PublicIds;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Token {
+    id: String,
+    name: String,
+    birth_date: Option<NaiveDate>,
+}
+
+pub fn encode(cursor: &Cursor, ids: &PublicIds) -> String {
+    let token = Token {
+        id: ids.encode(&cursor.id),
+        name: cursor.name.clone(),
+        birth_date: cursor.birth_date,
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&token).unwrap_or_default())
+}
+
+/// The cursor `token` holds, failing validation of the `cursor` field if it holds none.
+pub fn decode(token: &str, ids: &PublicIds) -> Result<Cursor, DomainError> {
+    URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|x| serde_json::from_slice::<Token>(&x).ok())
+        .and_then(|x| {
+            Some(Cursor {
+                id: ids.decode(&x.id)?,
+                name: x.name,
+                birth_date: x.birth_date,
+            })
+        })
+        .ok_or_else(|| {
+            let mut errors = ValidationErrors::new();
+            errors.add("cursor", ValidationError::new("cursor"));
+            errors.into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::user::UserId;
+
+    use super::*;
+
+    fn cursor() -> Cursor {
+        Cursor {
+            id: UserId(123),
+            name: "name".into(),
+            birth_date: NaiveDate::from_ymd_opt(2000, 1, 2),
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() -> anyhow::Result<()> {
+        let ids = PublicIds::new("secret");
+
+        assert_eq!(decode(&encode(&cursor(), &ids), &ids)?, cursor());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_hides_id() -> anyhow::Result<()> {
+        let ids = PublicIds::new("secret");
+
+        let token: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(encode(&cursor(), &ids))?)?;
+
+        assert_eq!(token["id"], serde_json::json!(ids.encode(&UserId(123))));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("!!!")]
+    #[case("e30")]
+    // `{"id":123,"name":"name","birth_date":null}`, a raw id.
+    #[case("eyJpZCI6MTIzLCJuYW1lIjoibmFtZSIsImJpcnRoX2RhdGUiOm51bGx9")]
+    fn test_cursor_decode_invalid(#[case] token: &str) {
+        assert_matches!(decode(token, &PublicIds::new("secret")), Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "cursor"));
+        });
+    }
+}
@@ -0,0 +1,215 @@
+//! Writers that encode a stream of users one row at a time, shared by the CLI and the web API.
+
+use std::str::FromStr;
+
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use serde::Deserialize;
+
+use chrono::NaiveDate;
+
+use crate::domain::{error::Result, user::User};
+
+use super::{presenter::UserView, public_id::PublicIds};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Comma separated values with a header row.
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+    /// A single JSON array.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "ndjson" => Ok(Self::Ndjson),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+const CSV_HEADER: [&str; 7] = [
+    "id",
+    "name",
+    "email",
+    "birth_date",
+    "age",
+    "deleted_at",
+    "version",
+];
+
+/// Encodes users in `format` as [`UserView`]s with their ages on `today`, yielding a chunk per user
+/// plus any framing before and after.
+pub fn encode<'a>(
+    format: ExportFormat,
+    users: BoxStream<'a, Result<User>>,
+    ids: PublicIds,
+    today: NaiveDate,
+) -> BoxStream<'a, Result<Vec<u8>>> {
+    let users = users.map(move |x| x.map(|user| UserView::new(user, &ids, today)));
+    match format {
+        ExportFormat::Csv => stream::once(async { csv_record(CSV_HEADER) })
+            .chain(users.map(|x| {
+                let user = x?;
+                csv_record([
+                    user.id,
+                    user.name,
+                    user.email.unwrap_or_default(),
+                    user.birth_date.map(|x| x.to_string()).unwrap_or_default(),
+                    user.age.map(|x| x.to_string()).unwrap_or_default(),
+                    user.deleted_at.map(|x| x.to_rfc3339()).unwrap_or_default(),
+                    user.version.0.to_string(),
+                ])
+            }))
+            .boxed(),
+        ExportFormat::Ndjson => users
+            .map(|x| {
+                let mut line = json(&x?)?;
+                line.push(b'\n');
+                Ok(line)
+            })
+            .boxed(),
+        ExportFormat::Json => stream::once(async { Ok(b"[".to_vec()) })
+            .chain(users.enumerate().map(|(i, x)| {
+                let mut item = if i == 0 { vec![] } else { b",".to_vec() };
+                item.extend(json(&x?)?);
+                Ok(item)
+            }))
+            .chain(stream::once(async { Ok(b"]\n".to_vec()) }))
+            .boxed(),
+    }
+}
+
+fn csv_record<I: IntoIterator<Item = T>, T: AsRef<[u8]>>(record: I) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(record).map_err(anyhow::Error::from)?;
+    Ok(writer.into_inner().map_err(anyhow::Error::from)?)
+}
+
+fn json(user: &UserView) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(user).map_err(anyhow::Error::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::{TimeZone, Utc};
+    use futures::TryStreamExt;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::{
+        error::DomainError,
+        user::{UserId, Version},
+    };
+
+    use super::*;
+
+    fn users() -> Vec<User> {
+        vec![
+            User {
+                id: UserId(1),
+                name: "Taro, Jr.".into(),
+                email: Some("taro@example.com".into()),
+                birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+                deleted_at: None,
+                version: Version::INITIAL,
+            },
+            User {
+                id: UserId(2),
+                name: "Hanako".into(),
+                email: None,
+                birth_date: None,
+                deleted_at: Some(Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap()),
+                version: Version(2),
+            },
+        ]
+    }
+
+    async fn encode_to_string(format: ExportFormat, users: Vec<User>) -> anyhow::Result<String> {
+        let chunks = encode(
+            format,
+            stream::iter(users.into_iter().map(Ok)).boxed(),
+            PublicIds::new("secret"),
+            NaiveDate::from_ymd_opt(2023, 6, 15).unwrap(),
+        )
+        .try_collect::<Vec<_>>()
+        .await?;
+        Ok(String::from_utf8(chunks.concat())?)
+    }
+
+    #[rstest]
+    #[case(
+        ExportFormat::Csv,
+        "id,name,email,birth_date,age,deleted_at,version\n\
+         4PA8CwFUw3y,\"Taro, Jr.\",taro@example.com,2003-01-01,20,,1\n\
+         0aTWFTsNWC5,Hanako,,,,2023-01-02T03:04:05+00:00,2\n"
+    )]
+    #[case(
+        ExportFormat::Ndjson,
+        "{\"id\":\"4PA8CwFUw3y\",\"name\":\"Taro, Jr.\",\"email\":\"taro@example.com\",\"birth_date\":\"2003-01-01\",\"age\":20,\"deleted_at\":null,\"version\":1}\n\
+         {\"id\":\"0aTWFTsNWC5\",\"name\":\"Hanako\",\"email\":null,\"birth_date\":null,\"age\":null,\"deleted_at\":\"2023-01-02T03:04:05Z\",\"version\":2}\n"
+    )]
+    #[case(
+        ExportFormat::Json,
+        "[{\"id\":\"4PA8CwFUw3y\",\"name\":\"Taro, Jr.\",\"email\":\"taro@example.com\",\"birth_date\":\"2003-01-01\",\"age\":20,\"deleted_at\":null,\"version\":1},\
+         {\"id\":\"0aTWFTsNWC5\",\"name\":\"Hanako\",\"email\":null,\"birth_date\":null,\"age\":null,\"deleted_at\":\"2023-01-02T03:04:05Z\",\"version\":2}]\n"
+    )]
+    #[tokio::test]
+    async fn test_encode(
+        #[case] format: ExportFormat,
+        #[case] expected: &str,
+    ) -> anyhow::Result<()> {
+        assert_eq!(encode_to_string(format, users()).await?, expected);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(ExportFormat::Csv, "id,name,email,birth_date,age,deleted_at,version\n")]
+    #[case(ExportFormat::Ndjson, "")]
+    #[case(ExportFormat::Json, "[]\n")]
+    #[tokio::test]
+    async fn test_encode_empty(
+        #[case] format: ExportFormat,
+        #[case] expected: &str,
+    ) -> anyhow::Result<()> {
+        assert_eq!(encode_to_string(format, vec![]).await?, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encode_error() {
+        let res = encode(
+            ExportFormat::Json,
+            stream::iter([Err(DomainError::Unavailable("down".into()))]).boxed(),
+            PublicIds::new("secret"),
+            NaiveDate::from_ymd_opt(2023, 6, 15).unwrap(),
+        )
+        .try_collect::<Vec<_>>()
+        .await;
+
+        assert_matches!(res, Err(DomainError::Unavailable(_)));
+    }
+}
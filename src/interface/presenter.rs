@@ -0,0 +1,154 @@
+//! What clients see of domain objects: the same fields, but with user ids in their public form and
+//! ages computed for the day of the request.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::domain::{
+    audit::{Actor, AuditEntry, Operation},
+    credential::{Session, SessionToken},
+    pagination::Page,
+    user::{search::SearchHit, User, Version},
+};
+
+use super::{cursor, public_id::PublicIds};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserView {
+    pub id: String,
+    pub name: String,
+    pub email: Option<String>,
+    pub birth_date: Option<NaiveDate>,
+    pub age: Option<u32>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: Version,
+}
+
+impl UserView {
+    pub fn new(user: User, ids: &PublicIds, today: NaiveDate) -> Self {
+        Self {
+            id: ids.encode(&user.id),
+            age: user.age(today),
+            name: user.name,
+            email: user.email,
+            birth_date: user.birth_date,
+            deleted_at: user.deleted_at,
+            version: user.version,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageView {
+    pub items: Vec<UserView>,
+    pub next_cursor: Option<String>,
+}
+
+impl PageView {
+    pub fn new(page: Page<User>, ids: &PublicIds, today: NaiveDate) -> Self {
+        Self {
+            next_cursor: page.next_cursor.map(|x| cursor::encode(&x, ids)),
+            items: page
+                .items
+                .into_iter()
+                .map(|x| UserView::new(x, ids, today))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHitView {
+    #[serde(flatten)]
+    pub user: UserView,
+    pub score: f32,
+}
+
+impl SearchHitView {
+    pub fn new(hit: SearchHit, ids: &PublicIds, today: NaiveDate) -> Self {
+        Self {
+            user: UserView::new(hit.user, ids, today),
+            score: hit.score,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntryView {
+    pub id: i64,
+    pub user_id: String,
+    pub actor: Actor,
+    pub operation: Operation,
+    pub diff: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntryView {
+    pub fn new(entry: AuditEntry, ids: &PublicIds) -> Self {
+        Self {
+            id: entry.id,
+            user_id: ids.encode(&entry.user_id),
+            actor: entry.actor,
+            operation: entry.operation,
+            diff: entry.diff,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+/// A new session as an OAuth 2.0 style bearer token response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionView {
+    pub token: SessionToken,
+    pub token_type: &'static str,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SessionView {
+    pub fn new(session: Session) -> Self {
+        Self {
+            token: session.token,
+            token_type: "Bearer",
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::domain::user::UserId;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_user() -> anyhow::Result<()> {
+        let ids = PublicIds::new("secret");
+        let user = User {
+            id: UserId(1),
+            name: "name".into(),
+            email: Some("name@example.com".into()),
+            birth_date: NaiveDate::from_ymd_opt(2000, 6, 15),
+            deleted_at: None,
+            version: Version::INITIAL,
+        };
+        let today = NaiveDate::from_ymd_opt(2023, 6, 14).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(UserView::new(user, &ids, today))?,
+            json!({
+                "id": ids.encode(&UserId(1)),
+                "name": "name",
+                "email": "name@example.com",
+                "birth_date": "2000-06-15",
+                "age": 22,
+                "deleted_at": null,
+                "version": 1,
+            })
+        );
+
+        Ok(())
+    }
+}
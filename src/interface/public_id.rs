@@ -0,0 +1,130 @@
+//! Opaque user ids for clients, so that ids reveal neither how many users there are nor which ids
+//! their neighbours have.
+
+use crate::domain::user::UserId;
+
+const ALPHABET: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+/// Enough base62 digits for any 64-bit value, so every public id has the same length.
+const LEN: usize = 11;
+const ROUNDS: usize = 6;
+
+/// Reversible, keyed encoding of [`UserId`]s, in the style of hashids.
+///
+/// Ids are permuted by a Feistel network keyed by the secret and written in base62. This is
+/// obfuscation rather than encryption; it stops enumeration, not a determined attacker. Changing
+/// the secret changes every public id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicIds {
+    keys: [u32; ROUNDS],
+}
+
+impl PublicIds {
+    pub fn new(secret: &str) -> Self {
+        let mut keys = [0; ROUNDS];
+        for (i, key) in keys.iter_mut().enumerate() {
+            *key = fnv1a(secret.bytes().chain([i as u8])) as u32;
+        }
+        Self { keys }
+    }
+
+    pub fn encode(&self, id: &UserId) -> String {
+        let (mut left, mut right) = split(id.0 as u64);
+        for key in self.keys {
+            (left, right) = (right, left ^ round(right, key));
+        }
+        let mut value = join(left, right);
+        let mut digits = [b'0'; LEN];
+        for digit in digits.iter_mut().rev() {
+            *digit = ALPHABET[(value % 62) as usize];
+            value /= 62;
+        }
+        String::from_utf8_lossy(&digits).into_owned()
+    }
+
+    /// The id encoded as `s`, if `s` is a public id at all.
+    pub fn decode(&self, s: &str) -> Option<UserId> {
+        if s.len() != LEN {
+            return None;
+        }
+        let value = s.bytes().try_fold(0u128, |value, x| {
+            let digit = ALPHABET.iter().position(|&c| c == x)?;
+            Some(value * 62 + digit as u128)
+        })?;
+        let (mut left, mut right) = split(u64::try_from(value).ok()?);
+        for key in self.keys.into_iter().rev() {
+            (left, right) = (right ^ round(left, key), left);
+        }
+        Some(UserId(join(left, right) as i64))
+    }
+}
+
+fn split(value: u64) -> (u32, u32) {
+    ((value >> 32) as u32, value as u32)
+}
+
+fn join(left: u32, right: u32) -> u64 {
+    u64::from(left) << 32 | u64::from(right)
+}
+
+/// The finalizer of MurmurHash3, which mixes every input bit into every output bit.
+fn round(value: u32, key: u32) -> u32 {
+    let mut x = value ^ key;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2_ae35);
+    x ^ (x >> 16)
+}
+
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, x| {
+        (hash ^ u64::from(x)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_ne};
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(2)]
+    #[case(1 << 40)]
+    #[case(i64::MAX)]
+    #[case(-1)]
+    fn test_round_trip(#[case] id: i64) {
+        let ids = PublicIds::new("secret");
+
+        let encoded = ids.encode(&UserId(id));
+
+        assert_eq!(encoded.len(), LEN);
+        assert_eq!(ids.decode(&encoded), Some(UserId(id)));
+    }
+
+    #[test]
+    fn test_encode_hides_order() {
+        let ids = PublicIds::new("secret");
+
+        let encoded = (1..=3).map(|x| ids.encode(&UserId(x))).collect::<Vec<_>>();
+
+        assert!(!encoded.windows(2).all(|x| x[0] < x[1]));
+        assert_ne!(
+            PublicIds::new("other").encode(&UserId(1)),
+            ids.encode(&UserId(1))
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("1")]
+    #[case("00000000000000")]
+    #[case("0000000000-")]
+    #[case("zzzzzzzzzzz")]
+    fn test_decode_invalid(#[case] s: &str) {
+        assert_eq!(PublicIds::new("secret").decode(s), None);
+    }
+}
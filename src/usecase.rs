@@ -1 +1,4 @@
+pub mod api_key;
+pub mod auth;
+pub mod policy;
 pub mod user;
@@ -0,0 +1,4 @@
+pub mod authenticate;
+pub mod create;
+pub mod list;
+pub mod revoke;
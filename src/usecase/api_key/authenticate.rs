@@ -0,0 +1,118 @@
+use chrono::{Duration, Utc};
+
+use crate::domain::{
+    api_key::{ApiKey, ApiKeySecret},
+    error::{DomainError, Result},
+    repository::api_key_repository::ApiKeyRepository,
+};
+
+/// How many seconds `last_used_at` may lag, so that busy keys are not written on every request.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+pub struct AuthenticateApiKey<'a, R: ApiKeyRepository> {
+    repo: &'a R,
+}
+
+impl<'a, R: ApiKeyRepository> AuthenticateApiKey<'a, R> {
+    pub fn new(repo: &'a R) -> Self {
+        Self { repo }
+    }
+
+    /// The active key with the secret, or [`DomainError::InvalidCredentials`] for unknown,
+    /// expired and revoked ones alike.
+    pub async fn run(&self, secret: &ApiKeySecret) -> Result<ApiKey> {
+        let now = Utc::now();
+        let mut key = self
+            .repo
+            .find_api_key(secret)
+            .await?
+            .filter(|x| x.is_active(now))
+            .ok_or(DomainError::InvalidCredentials)?;
+        if key
+            .last_used_at
+            .map_or(true, |x| x + Duration::seconds(TOUCH_INTERVAL_SECS) <= now)
+        {
+            self.repo.touch_api_key(&key.id, now).await?;
+            key.last_used_at = Some(now);
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mockall::predicate::{always, eq};
+    use rstest::rstest;
+
+    use crate::domain::{
+        api_key::{ApiKeyId, Scope},
+        repository::api_key_repository::MockApiKeyRepository,
+    };
+
+    use super::*;
+
+    fn key(last_used: Option<Duration>) -> ApiKey {
+        let now = Utc::now();
+        ApiKey {
+            id: ApiKeyId(100),
+            prefix: "exk_abcdefgh".into(),
+            owner: "batch".into(),
+            scopes: vec![Scope::UsersRead],
+            expires_at: None,
+            last_used_at: last_used.map(|x| now - x),
+            revoked_at: None,
+            created_at: now,
+        }
+    }
+
+    #[rstest]
+    #[case(None, true)]
+    #[case(Some(Duration::minutes(5)), true)]
+    #[case(Some(Duration::seconds(5)), false)]
+    #[tokio::test]
+    async fn test_authenticate_api_key(
+        #[case] last_used: Option<Duration>,
+        #[case] touched: bool,
+    ) -> anyhow::Result<()> {
+        let secret = ApiKeySecret("exk_secret".into());
+
+        let mut repo = MockApiKeyRepository::new();
+        repo.expect_find_api_key()
+            .with(eq(secret.clone()))
+            .returning(move |_| Ok(Some(key(last_used))));
+        repo.expect_touch_api_key()
+            .with(eq(ApiKeyId(100)), always())
+            .times(usize::from(touched))
+            .returning(|_, _| Ok(()));
+
+        let key = AuthenticateApiKey::new(&repo).run(&secret).await?;
+
+        assert_eq!(key.id, ApiKeyId(100));
+        assert!(key.last_used_at.is_some());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(ApiKey { revoked_at: Some(Utc::now()), ..key(None) }))]
+    #[case(Some(ApiKey { expires_at: Some(Utc::now() - Duration::seconds(1)), ..key(None) }))]
+    #[tokio::test]
+    async fn test_authenticate_api_key_if_inactive(
+        #[case] found: Option<ApiKey>,
+    ) -> anyhow::Result<()> {
+        let mut repo = MockApiKeyRepository::new();
+        repo.expect_find_api_key()
+            .returning(move |_| Ok(found.clone()));
+        repo.expect_touch_api_key().never();
+
+        let res = AuthenticateApiKey::new(&repo)
+            .run(&ApiKeySecret("exk_secret".into()))
+            .await;
+
+        assert_matches!(res, Err(DomainError::InvalidCredentials));
+
+        Ok(())
+    }
+}
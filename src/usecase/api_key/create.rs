@@ -0,0 +1,137 @@
+use validator::Validate;
+
+use crate::domain::{
+    api_key::{ApiKey, ApiKeyId, ApiKeySecret, NewApiKey},
+    credential::TokenGenerator,
+    error::Result,
+    repository::api_key_repository::ApiKeyRepository,
+};
+
+pub struct CreateApiKey<'a, R: ApiKeyRepository> {
+    repo: &'a R,
+    tokens: &'a dyn TokenGenerator,
+}
+
+impl<'a, R: ApiKeyRepository> CreateApiKey<'a, R> {
+    pub fn new(repo: &'a R, tokens: &'a dyn TokenGenerator) -> Self {
+        Self { repo, tokens }
+    }
+
+    /// Creates the key along with its secret, which cannot be had again later.
+    pub async fn run(&self, key: NewApiKey) -> Result<(ApiKey, ApiKeySecret)> {
+        key.validate()?;
+        let secret = ApiKeySecret::generate(self.tokens);
+        let key = self.repo.create_api_key(key, &secret).await?;
+        Ok((key, secret))
+    }
+
+    /// Replaces the key with a new one that may do the same, revoking the old one.
+    pub async fn rotate(&self, id: &ApiKeyId) -> Result<(ApiKey, ApiKeySecret)> {
+        let secret = ApiKeySecret::generate(self.tokens);
+        let key = self.repo.rotate_api_key(id, &secret).await?;
+        Ok((key, secret))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use mockall::predicate::{always, eq};
+
+    use crate::domain::{
+        api_key::Scope, credential::MockTokenGenerator, error::DomainError,
+        repository::api_key_repository::MockApiKeyRepository,
+    };
+
+    use super::*;
+
+    fn tokens() -> MockTokenGenerator {
+        let mut tokens = MockTokenGenerator::new();
+        tokens
+            .expect_random_token()
+            .returning(|| "abcdefghijklmnop".into());
+        tokens
+    }
+
+    fn stored(key: NewApiKey, secret: &ApiKeySecret) -> ApiKey {
+        ApiKey {
+            id: ApiKeyId(100),
+            prefix: secret.prefix().into(),
+            owner: key.owner,
+            scopes: key.scopes,
+            expires_at: key.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_api_key() -> anyhow::Result<()> {
+        let new_key = NewApiKey {
+            owner: "batch".into(),
+            scopes: vec![Scope::UsersRead],
+            expires_at: None,
+        };
+
+        let mut repo = MockApiKeyRepository::new();
+        repo.expect_create_api_key()
+            .with(eq(new_key.clone()), always())
+            .returning(|x, secret| Ok(stored(x, secret)));
+
+        let (key, secret) = CreateApiKey::new(&repo, &tokens()).run(new_key).await?;
+
+        assert!(ApiKeySecret::is_api_key(&secret.0));
+        assert_eq!(key.prefix, secret.prefix());
+        assert_eq!(key.owner, "batch");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_api_key_if_validation_error() -> anyhow::Result<()> {
+        let mut repo = MockApiKeyRepository::new();
+        repo.expect_create_api_key().never();
+
+        let res = CreateApiKey::new(&repo, &tokens())
+            .run(NewApiKey {
+                owner: "".into(),
+                scopes: vec![],
+                expires_at: None,
+            })
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(e.field_errors().contains_key("owner"));
+            assert!(e.field_errors().contains_key("scopes"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotate_api_key() -> anyhow::Result<()> {
+        let mut repo = MockApiKeyRepository::new();
+        repo.expect_rotate_api_key()
+            .with(eq(ApiKeyId(1)), always())
+            .returning(|_, secret| {
+                Ok(stored(
+                    NewApiKey {
+                        owner: "batch".into(),
+                        scopes: vec![Scope::UsersRead],
+                        expires_at: None,
+                    },
+                    secret,
+                ))
+            });
+
+        let (key, secret) = CreateApiKey::new(&repo, &tokens())
+            .rotate(&ApiKeyId(1))
+            .await?;
+
+        assert_eq!(key.prefix, secret.prefix());
+
+        Ok(())
+    }
+}
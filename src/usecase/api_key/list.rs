@@ -0,0 +1,17 @@
+use crate::domain::{
+    api_key::ApiKey, error::Result, repository::api_key_repository::ApiKeyRepository,
+};
+
+pub struct ListApiKeys<'a, R: ApiKeyRepository> {
+    repo: &'a R,
+}
+
+impl<'a, R: ApiKeyRepository> ListApiKeys<'a, R> {
+    pub fn new(repo: &'a R) -> Self {
+        Self { repo }
+    }
+
+    pub async fn run(&self) -> Result<Vec<ApiKey>> {
+        self.repo.list_api_keys().await
+    }
+}
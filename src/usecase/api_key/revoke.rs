@@ -0,0 +1,19 @@
+use crate::domain::{
+    api_key::{ApiKey, ApiKeyId},
+    error::Result,
+    repository::api_key_repository::ApiKeyRepository,
+};
+
+pub struct RevokeApiKey<'a, R: ApiKeyRepository> {
+    repo: &'a R,
+}
+
+impl<'a, R: ApiKeyRepository> RevokeApiKey<'a, R> {
+    pub fn new(repo: &'a R) -> Self {
+        Self { repo }
+    }
+
+    pub async fn run(&self, id: &ApiKeyId) -> Result<ApiKey> {
+        self.repo.revoke_api_key(id).await
+    }
+}
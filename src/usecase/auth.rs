@@ -0,0 +1,4 @@
+pub mod login;
+pub mod roles;
+pub mod session;
+pub mod set_password;
@@ -0,0 +1,202 @@
+use chrono::{Duration, Utc};
+
+use crate::domain::{
+    credential::{Credentials, PasswordHasher, Session, SessionToken, Sessions, TokenGenerator},
+    error::{DomainError, Result},
+    repository::credential_repository::CredentialRepository,
+};
+
+pub struct Login<'a, R: CredentialRepository> {
+    repo: &'a R,
+    hasher: &'a dyn PasswordHasher,
+    tokens: &'a dyn TokenGenerator,
+    ttl: Duration,
+}
+
+impl<'a, R: CredentialRepository> Login<'a, R> {
+    pub fn new(
+        repo: &'a R,
+        hasher: &'a dyn PasswordHasher,
+        tokens: &'a dyn TokenGenerator,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            repo,
+            hasher,
+            tokens,
+            ttl,
+        }
+    }
+
+    /// Starts a session for the user with the credentials. Hashes made with outdated parameters
+    /// are replaced while the password is at hand.
+    pub async fn run(&self, credentials: Credentials) -> Result<Session> {
+        let Some(credential) = self
+            .repo
+            .find_credential_by_email(&credentials.email)
+            .await?
+        else {
+            // Spend as long as a wrong password would, so timing does not tell which addresses
+            // have an account.
+            self.hasher.hash(&credentials.password).await?;
+            return Err(DomainError::InvalidCredentials);
+        };
+        if !self
+            .hasher
+            .verify(&credentials.password, &credential.password_hash)
+            .await?
+        {
+            return Err(DomainError::InvalidCredentials);
+        }
+        if self.hasher.needs_rehash(&credential.password_hash) {
+            let hash = self.hasher.hash(&credentials.password).await?;
+            self.repo
+                .set_password_hash(&credential.user_id, &hash, Sessions::Keep)
+                .await?;
+        }
+
+        let session = Session {
+            token: SessionToken::generate(self.tokens),
+            user_id: credential.user_id,
+            expires_at: Utc::now() + self.ttl,
+        };
+        self.repo.create_session(&session).await?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mockall::predicate::{always, eq};
+
+    use crate::domain::{
+        credential::{Credential, MockPasswordHasher, MockTokenGenerator, PasswordHash},
+        repository::credential_repository::MockCredentialRepository,
+        user::UserId,
+    };
+
+    use super::*;
+
+    fn credentials(password: &str) -> Credentials {
+        Credentials {
+            email: "taro@example.com".into(),
+            password: password.into(),
+        }
+    }
+
+    fn tokens() -> MockTokenGenerator {
+        let mut tokens = MockTokenGenerator::new();
+        tokens.expect_random_token().returning(|| "random".into());
+        tokens
+    }
+
+    fn found(repo: &mut MockCredentialRepository) {
+        repo.expect_find_credential_by_email()
+            .with(eq("taro@example.com"))
+            .returning(|_| {
+                Ok(Some(Credential {
+                    user_id: UserId(100),
+                    password_hash: PasswordHash("$argon2id$old".into()),
+                }))
+            });
+    }
+
+    #[tokio::test]
+    async fn test_login() -> anyhow::Result<()> {
+        let mut repo = MockCredentialRepository::new();
+        found(&mut repo);
+        repo.expect_set_password_hash().never();
+        repo.expect_create_session()
+            .withf(|x| x.user_id == UserId(100))
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut hasher = MockPasswordHasher::new();
+        hasher
+            .expect_verify()
+            .with(
+                eq("correct horse"),
+                eq(PasswordHash("$argon2id$old".into())),
+            )
+            .returning(|_, _| Ok(true));
+        hasher.expect_needs_rehash().returning(|_| false);
+
+        let before = Utc::now();
+        let session = Login::new(&repo, &hasher, &tokens(), Duration::hours(1))
+            .run(credentials("correct horse"))
+            .await?;
+
+        assert_eq!(session.user_id, UserId(100));
+        assert!(session.expires_at >= before + Duration::hours(1));
+        assert!(session.expires_at <= Utc::now() + Duration::hours(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_login_rehashes() -> anyhow::Result<()> {
+        let mut repo = MockCredentialRepository::new();
+        found(&mut repo);
+        repo.expect_set_password_hash()
+            .with(
+                eq(UserId(100)),
+                eq(PasswordHash("$argon2id$new".into())),
+                eq(Sessions::Keep),
+            )
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        repo.expect_create_session().returning(|_| Ok(()));
+        let mut hasher = MockPasswordHasher::new();
+        hasher.expect_verify().returning(|_, _| Ok(true));
+        hasher.expect_needs_rehash().returning(|_| true);
+        hasher
+            .expect_hash()
+            .with(eq("correct horse"))
+            .returning(|_| Ok(PasswordHash("$argon2id$new".into())));
+
+        Login::new(&repo, &hasher, &tokens(), Duration::hours(1))
+            .run(credentials("correct horse"))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_login_if_wrong_password() -> anyhow::Result<()> {
+        let mut repo = MockCredentialRepository::new();
+        found(&mut repo);
+        repo.expect_create_session().never();
+        let mut hasher = MockPasswordHasher::new();
+        hasher.expect_verify().returning(|_, _| Ok(false));
+
+        let res = Login::new(&repo, &hasher, &tokens(), Duration::hours(1))
+            .run(credentials("wrong horse"))
+            .await;
+
+        assert_matches!(res, Err(DomainError::InvalidCredentials));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_login_if_unknown_email() -> anyhow::Result<()> {
+        let mut repo = MockCredentialRepository::new();
+        repo.expect_find_credential_by_email()
+            .returning(|_| Ok(None));
+        repo.expect_create_session().never();
+        let mut hasher = MockPasswordHasher::new();
+        hasher
+            .expect_hash()
+            .with(always())
+            .times(1)
+            .returning(|_| Ok(PasswordHash("$argon2id$hash".into())));
+
+        let res = Login::new(&repo, &hasher, &tokens(), Duration::hours(1))
+            .run(credentials("correct horse"))
+            .await;
+
+        assert_matches!(res, Err(DomainError::InvalidCredentials));
+
+        Ok(())
+    }
+}
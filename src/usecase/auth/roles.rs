@@ -0,0 +1,64 @@
+use crate::domain::{
+    error::{DomainError, Result},
+    repository::role_repository::RoleRepository,
+    role::Role,
+    user::UserId,
+};
+
+/// Looks up what an authenticated user may do. Part of authentication, so it is not itself
+/// authorized.
+pub struct GetRoles<'a, R: RoleRepository> {
+    repo: &'a R,
+}
+
+impl<'a, R: RoleRepository> GetRoles<'a, R> {
+    pub fn new(repo: &'a R) -> Self {
+        Self { repo }
+    }
+
+    /// Fails with [`DomainError::InvalidCredentials`] if the user does not exist or is deleted,
+    /// since their tokens no longer stand for anyone.
+    pub async fn run(&self, id: &UserId) -> Result<Vec<Role>> {
+        self.repo
+            .find_roles(id)
+            .await?
+            .ok_or(DomainError::InvalidCredentials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mockall::predicate::eq;
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::repository::role_repository::MockRoleRepository;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_roles() -> anyhow::Result<()> {
+        let mut repo = MockRoleRepository::new();
+        repo.expect_find_roles()
+            .with(eq(UserId(100)))
+            .returning(|_| Ok(Some(vec![Role::Viewer])));
+
+        let roles = GetRoles::new(&repo).run(&UserId(100)).await?;
+
+        assert_eq!(roles, vec![Role::Viewer]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_roles_if_not_found() {
+        let mut repo = MockRoleRepository::new();
+        repo.expect_find_roles()
+            .with(eq(UserId(100)))
+            .returning(|_| Ok(None));
+
+        let res = GetRoles::new(&repo).run(&UserId(100)).await;
+
+        assert_matches!(res, Err(DomainError::InvalidCredentials));
+    }
+}
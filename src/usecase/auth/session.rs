@@ -0,0 +1,87 @@
+use chrono::Utc;
+
+use crate::domain::{
+    credential::{Session, SessionToken},
+    error::{DomainError, Result},
+    repository::credential_repository::CredentialRepository,
+};
+
+pub struct AuthenticateSession<'a, R: CredentialRepository> {
+    repo: &'a R,
+}
+
+impl<'a, R: CredentialRepository> AuthenticateSession<'a, R> {
+    pub fn new(repo: &'a R) -> Self {
+        Self { repo }
+    }
+
+    /// The active session with the token, or [`DomainError::InvalidCredentials`] for unknown and
+    /// expired ones alike.
+    pub async fn run(&self, token: &SessionToken) -> Result<Session> {
+        let now = Utc::now();
+        self.repo
+            .find_session(token)
+            .await?
+            .filter(|x| x.is_active(now))
+            .ok_or(DomainError::InvalidCredentials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::Duration;
+    use mockall::predicate::eq;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::{
+        repository::credential_repository::MockCredentialRepository, user::UserId,
+    };
+
+    use super::*;
+
+    fn session(ttl: Duration) -> Session {
+        Session {
+            token: SessionToken("exs_token".into()),
+            user_id: UserId(100),
+            expires_at: Utc::now() + ttl,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_session() -> anyhow::Result<()> {
+        let mut repo = MockCredentialRepository::new();
+        repo.expect_find_session()
+            .with(eq(SessionToken("exs_token".into())))
+            .returning(|_| Ok(Some(session(Duration::hours(1)))));
+
+        let session = AuthenticateSession::new(&repo)
+            .run(&SessionToken("exs_token".into()))
+            .await?;
+
+        assert_eq!(session.user_id, UserId(100));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(session(Duration::seconds(-1))))]
+    #[tokio::test]
+    async fn test_authenticate_session_if_inactive(
+        #[case] found: Option<Session>,
+    ) -> anyhow::Result<()> {
+        let mut repo = MockCredentialRepository::new();
+        repo.expect_find_session()
+            .returning(move |_| Ok(found.clone()));
+
+        let res = AuthenticateSession::new(&repo)
+            .run(&SessionToken("exs_token".into()))
+            .await;
+
+        assert_matches!(res, Err(DomainError::InvalidCredentials));
+
+        Ok(())
+    }
+}
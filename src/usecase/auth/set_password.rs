@@ -0,0 +1,148 @@
+use validator::ValidateArgs;
+
+use crate::{
+    domain::{
+        credential::{NewPassword, PasswordHasher, PasswordPolicy, Sessions},
+        error::Result,
+        repository::credential_repository::CredentialRepository,
+        user::UserId,
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct SetPassword<'a, R: CredentialRepository> {
+    repo: &'a R,
+    hasher: &'a dyn PasswordHasher,
+    policy: &'a PasswordPolicy,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: CredentialRepository> SetPassword<'a, R> {
+    pub fn new(
+        repo: &'a R,
+        hasher: &'a dyn PasswordHasher,
+        policy: &'a PasswordPolicy,
+        auth: &'a dyn Authorizer,
+    ) -> Self {
+        Self {
+            repo,
+            hasher,
+            policy,
+            auth,
+        }
+    }
+
+    /// Also signs the user out of every session, as whoever knew the old password may hold one.
+    pub async fn run(&self, subject: &Subject, id: &UserId, password: NewPassword) -> Result<()> {
+        self.auth
+            .authorize(subject, Action::SetPassword(id.clone()))?;
+        password.validate_args(self.policy)?;
+        let hash = self.hasher.hash(&password.password).await?;
+        self.repo
+            .set_password_hash(id, &hash, Sessions::Revoke)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mockall::predicate::eq;
+    use validator::ValidationErrors;
+
+    use crate::{
+        domain::{
+            credential::{MockPasswordHasher, PasswordHash},
+            error::DomainError,
+            repository::credential_repository::MockCredentialRepository,
+        },
+        usecase::policy::fixtures::{allow, deny, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_password() -> anyhow::Result<()> {
+        let mut hasher = MockPasswordHasher::new();
+        hasher
+            .expect_hash()
+            .with(eq("correct horse"))
+            .returning(|_| Ok(PasswordHash("$argon2id$hash".into())));
+        let mut repo = MockCredentialRepository::new();
+        repo.expect_set_password_hash()
+            .with(
+                eq(UserId(100)),
+                eq(PasswordHash("$argon2id$hash".into())),
+                eq(Sessions::Revoke),
+            )
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let auth = allow(Action::SetPassword(UserId(100)));
+        let policy = PasswordPolicy::default();
+        SetPassword::new(&repo, &hasher, &policy, &auth)
+            .run(
+                &subject(),
+                &UserId(100),
+                NewPassword {
+                    password: "correct horse".into(),
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_password_if_validation_error() -> anyhow::Result<()> {
+        let mut hasher = MockPasswordHasher::new();
+        hasher.expect_hash().never();
+        let mut repo = MockCredentialRepository::new();
+        repo.expect_set_password_hash().never();
+
+        let auth = allow(Action::SetPassword(UserId(100)));
+        let policy = PasswordPolicy {
+            require_digit: true,
+            ..Default::default()
+        };
+        let res = SetPassword::new(&repo, &hasher, &policy, &auth)
+            .run(
+                &subject(),
+                &UserId(100),
+                NewPassword {
+                    password: "correct horse".into(),
+                },
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "password"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_password_if_forbidden() -> anyhow::Result<()> {
+        let mut hasher = MockPasswordHasher::new();
+        hasher.expect_hash().never();
+        let mut repo = MockCredentialRepository::new();
+        repo.expect_set_password_hash().never();
+
+        let auth = deny(Action::SetPassword(UserId(100)));
+        let policy = PasswordPolicy::default();
+        let res = SetPassword::new(&repo, &hasher, &policy, &auth)
+            .run(
+                &subject(),
+                &UserId(100),
+                NewPassword {
+                    password: "correct horse".into(),
+                },
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Forbidden));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Who may do what. Usecases ask an [`Authorizer`] before acting on behalf of a [`Subject`], so
+//! the rules hold however the usecase is reached.
+
+use std::fmt::Debug;
+
+use crate::domain::{
+    api_key::Scope,
+    error::{DomainError, Result},
+    role::Role,
+    user::UserId,
+};
+
+/// Whoever a usecase runs on behalf of.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subject {
+    /// A signed in user with the roles they hold.
+    User { id: UserId, roles: Vec<Role> },
+    /// A machine client, limited to the scopes of its key.
+    ApiKey { scopes: Vec<Scope> },
+    /// The application itself, such as the CLI run by someone with access to the database.
+    System,
+}
+
+/// What a subject wants to do, with the user it is done to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// List, search or export users.
+    ListUsers,
+    ReadUser(UserId),
+    ReadHistory(UserId),
+    /// Create or import users.
+    CreateUsers,
+    UpdateUser(UserId),
+    /// Delete, restore or purge a user.
+    DeleteUser(UserId),
+    SetPassword(UserId),
+    /// Grant or revoke roles.
+    ManageRoles,
+}
+
+impl Action {
+    /// Whether `id` is the user the action is done to, if there is one.
+    fn is_on(&self, id: &UserId) -> bool {
+        match self {
+            Action::ReadUser(x)
+            | Action::ReadHistory(x)
+            | Action::UpdateUser(x)
+            | Action::DeleteUser(x)
+            | Action::SetPassword(x) => x == id,
+            Action::ListUsers | Action::CreateUsers | Action::ManageRoles => false,
+        }
+    }
+
+    /// The scope a key needs, or `None` if only users can do this.
+    fn scope(&self) -> Option<Scope> {
+        match self {
+            Action::ListUsers | Action::ReadUser(_) | Action::ReadHistory(_) => {
+                Some(Scope::UsersRead)
+            }
+            Action::CreateUsers | Action::UpdateUser(_) | Action::DeleteUser(_) => {
+                Some(Scope::UsersWrite)
+            }
+            Action::SetPassword(_) | Action::ManageRoles => None,
+        }
+    }
+}
+
+#[cfg_attr(test, mockall::automock)]
+pub trait Authorizer: Debug + Send + Sync {
+    /// Fails with `Forbidden` unless `subject` may do `action`.
+    fn authorize(&self, subject: &Subject, action: Action) -> Result<()>;
+}
+
+/// The rules of this application: users may read and edit themselves and do whatever their roles
+/// allow to others, keys whatever their scopes allow.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Policy;
+
+impl Policy {
+    pub fn allows(&self, subject: &Subject, action: &Action) -> bool {
+        match subject {
+            Subject::User { id, roles } => {
+                is_own(id, action) || roles.iter().any(|x| grants(*x, action))
+            }
+            Subject::ApiKey { scopes } => action.scope().map_or(false, |x| scopes.contains(&x)),
+            Subject::System => true,
+        }
+    }
+}
+
+impl Authorizer for Policy {
+    fn authorize(&self, subject: &Subject, action: Action) -> Result<()> {
+        if self.allows(subject, &action) {
+            Ok(())
+        } else {
+            Err(DomainError::Forbidden)
+        }
+    }
+}
+
+/// Whether `action` is one a user may do to themselves, which is anything but deleting.
+fn is_own(id: &UserId, action: &Action) -> bool {
+    !matches!(action, Action::DeleteUser(_)) && action.is_on(id)
+}
+
+fn grants(role: Role, action: &Action) -> bool {
+    match role {
+        Role::Admin => true,
+        Role::Operator => !matches!(action, Action::SetPassword(_) | Action::ManageRoles),
+        Role::Viewer => matches!(
+            action,
+            Action::ListUsers | Action::ReadUser(_) | Action::ReadHistory(_)
+        ),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use mockall::predicate::eq;
+
+    use super::*;
+
+    /// An operator, who the mocks below expect to be asked about.
+    pub fn subject() -> Subject {
+        Subject::User {
+            id: UserId(1),
+            roles: vec![Role::Operator],
+        }
+    }
+
+    /// An authorizer that lets [`subject`] do `action`, once.
+    pub fn allow(action: Action) -> MockAuthorizer {
+        authorizer(action, || Ok(()))
+    }
+
+    /// An authorizer that forbids [`subject`] to do `action`, once.
+    pub fn deny(action: Action) -> MockAuthorizer {
+        authorizer(action, || Err(DomainError::Forbidden))
+    }
+
+    fn authorizer(action: Action, res: fn() -> Result<()>) -> MockAuthorizer {
+        let mut auth = MockAuthorizer::new();
+        auth.expect_authorize()
+            .with(eq(subject()), eq(action))
+            .times(1)
+            .returning(move |_, _| res());
+        auth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn user(roles: &[Role]) -> Subject {
+        Subject::User {
+            id: UserId(1),
+            roles: roles.to_vec(),
+        }
+    }
+
+    fn key(scopes: &[Scope]) -> Subject {
+        Subject::ApiKey {
+            scopes: scopes.to_vec(),
+        }
+    }
+
+    #[rstest]
+    #[case(user(&[]), Action::ReadUser(UserId(1)), true)]
+    #[case(user(&[]), Action::ReadHistory(UserId(1)), true)]
+    #[case(user(&[]), Action::UpdateUser(UserId(1)), true)]
+    #[case(user(&[]), Action::SetPassword(UserId(1)), true)]
+    #[case(user(&[]), Action::DeleteUser(UserId(1)), false)]
+    #[case(user(&[]), Action::ReadUser(UserId(2)), false)]
+    #[case(user(&[]), Action::UpdateUser(UserId(2)), false)]
+    #[case(user(&[]), Action::ListUsers, false)]
+    #[case(user(&[Role::Viewer]), Action::ListUsers, true)]
+    #[case(user(&[Role::Viewer]), Action::ReadHistory(UserId(2)), true)]
+    #[case(user(&[Role::Viewer]), Action::CreateUsers, false)]
+    #[case(user(&[Role::Viewer]), Action::UpdateUser(UserId(2)), false)]
+    #[case(user(&[Role::Operator]), Action::CreateUsers, true)]
+    #[case(user(&[Role::Operator]), Action::UpdateUser(UserId(2)), true)]
+    #[case(user(&[Role::Operator]), Action::DeleteUser(UserId(2)), true)]
+    #[case(user(&[Role::Operator]), Action::SetPassword(UserId(2)), false)]
+    #[case(user(&[Role::Operator]), Action::ManageRoles, false)]
+    #[case(user(&[Role::Viewer, Role::Admin]), Action::SetPassword(UserId(2)), true)]
+    #[case(user(&[Role::Admin]), Action::ManageRoles, true)]
+    #[case(key(&[Scope::UsersRead]), Action::ReadUser(UserId(2)), true)]
+    #[case(key(&[Scope::UsersRead]), Action::UpdateUser(UserId(2)), false)]
+    #[case(key(&[Scope::UsersWrite]), Action::DeleteUser(UserId(2)), true)]
+    #[case(key(&[Scope::UsersRead, Scope::UsersWrite]), Action::SetPassword(UserId(2)), false)]
+    #[case(Subject::System, Action::ManageRoles, true)]
+    fn test_allows(#[case] subject: Subject, #[case] action: Action, #[case] expected: bool) {
+        assert_eq!(Policy.allows(&subject, &action), expected);
+    }
+
+    #[test]
+    fn test_authorize() {
+        assert_matches!(
+            Policy.authorize(&user(&[Role::Admin]), Action::ManageRoles),
+            Ok(())
+        );
+        assert_matches!(
+            Policy.authorize(&user(&[Role::Viewer]), Action::ManageRoles),
+            Err(DomainError::Forbidden)
+        );
+    }
+}
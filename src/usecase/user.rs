@@ -1 +1,10 @@
 pub mod create;
+pub mod delete;
+pub mod export;
+pub mod get;
+pub mod history;
+pub mod import;
+pub mod list;
+pub mod roles;
+pub mod search;
+pub mod update;
@@ -1,55 +1,85 @@
-use validator::Validate;
-
-use crate::domain::{
-    repository::user_repository::UserRepository,
-    user::{NewUser, User},
+use validator::ValidateArgs;
+
+use crate::{
+    domain::{
+        clock::Clock,
+        error::Result,
+        event::UserEvent,
+        repository::user_repository::UserRepository,
+        user::{NewUser, User},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
 };
 
 pub struct CreateUser<'a, R: UserRepository> {
     repo: &'a R,
+    auth: &'a dyn Authorizer,
+    clock: &'a dyn Clock,
 }
 
 impl<'a, R: UserRepository> CreateUser<'a, R> {
-    pub fn new(repo: &'a R) -> Self {
-        Self { repo }
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer, clock: &'a dyn Clock) -> Self {
+        Self { repo, auth, clock }
     }
 
-    pub async fn run(&self, user: NewUser) -> anyhow::Result<User> {
-        user.validate()?;
-        self.repo.create_user(user).await
+    pub async fn run(&self, subject: &Subject, user: NewUser) -> Result<User> {
+        self.auth.authorize(subject, Action::CreateUsers)?;
+        user.validate_args(&self.clock.today())?;
+        self.repo.create_user(user, UserEvent::created).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
-    use mockall::predicate::eq;
+    use chrono::NaiveDate;
+    use mockall::predicate::{always, eq};
     use validator::ValidationErrors;
 
-    use crate::domain::{repository::user_repository::MockUserRepository, user::UserId};
+    use crate::{
+        domain::{
+            clock::FixedClock,
+            error::DomainError,
+            repository::user_repository::MockUserRepository,
+            user::{UserId, Version},
+        },
+        usecase::policy::fixtures::{allow, deny, subject},
+    };
 
     use super::*;
 
+    fn clock() -> FixedClock {
+        FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap())
+    }
+
     #[tokio::test]
     async fn test_create_user() -> anyhow::Result<()> {
         let new_user = NewUser {
             name: "TestName".into(),
-            age: 99,
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
         };
 
         let mut repo = MockUserRepository::new();
         repo.expect_create_user()
-            .with(eq(new_user.clone()))
-            .returning(|x| {
-                Ok(User {
+            .with(eq(new_user.clone()), always())
+            .returning(|x, raise| {
+                let user = User {
                     id: UserId(100),
                     name: x.name,
-                    age: x.age,
-                })
+                    email: x.email,
+                    birth_date: x.birth_date,
+                    deleted_at: None,
+                    version: Version::INITIAL,
+                };
+                assert_matches!(raise(&user), UserEvent::UserCreated(_));
+                Ok(user)
             });
 
-        let usecase = CreateUser::new(&repo);
-        let user = usecase.run(new_user).await?;
+        let auth = allow(Action::CreateUsers);
+        let clock = clock();
+        let usecase = CreateUser::new(&repo, &auth, &clock);
+        let user = usecase.run(&subject(), new_user).await?;
 
         assert_matches!(user, User { id, ..} => {
             assert_eq!(id, UserId(100));
@@ -62,30 +92,83 @@ mod tests {
     async fn test_create_user_if_validation_error() -> anyhow::Result<()> {
         let new_user = NewUser {
             name: "".into(),
-            age: 99,
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
         };
 
         let mut repo = MockUserRepository::new();
         repo.expect_create_user()
-            .with(eq(new_user.clone()))
-            .returning(|x| {
+            .with(eq(new_user.clone()), always())
+            .returning(|x, _| {
                 Ok(User {
                     id: UserId(100),
                     name: x.name,
-                    age: x.age,
+                    email: x.email,
+                    birth_date: x.birth_date,
+                    deleted_at: None,
+                    version: Version::INITIAL,
                 })
             });
 
-        let usecase = CreateUser::new(&repo);
-        let res = usecase.run(new_user).await;
+        let auth = allow(Action::CreateUsers);
+        let clock = clock();
+        let usecase = CreateUser::new(&repo, &auth, &clock);
+        let res = usecase.run(&subject(), new_user).await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name"));
+        });
+
+        Ok(())
+    }
 
-        assert_matches!(res, Err(e) => {
-            match e.downcast::<ValidationErrors>() {
-                Ok(e) => assert!(ValidationErrors::has_error(&Err(e), "name")),
-                Err(e) => panic!("Not ValidationErrors: {:?}", e),
-            }
+    #[tokio::test]
+    async fn test_create_user_if_born_after_today() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_create_user().never();
+
+        let auth = allow(Action::CreateUsers);
+        let clock = clock();
+        let usecase = CreateUser::new(&repo, &auth, &clock);
+        let res = usecase
+            .run(
+                &subject(),
+                NewUser {
+                    name: "TestName".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(2023, 6, 16),
+                },
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "birth_date"));
         });
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_create_user_if_forbidden() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_create_user().never();
+
+        let auth = deny(Action::CreateUsers);
+        let clock = clock();
+        let usecase = CreateUser::new(&repo, &auth, &clock);
+        let res = usecase
+            .run(
+                &subject(),
+                NewUser {
+                    name: "TestName".into(),
+                    email: None,
+                    birth_date: None,
+                },
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Forbidden));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,103 @@
+use crate::{
+    domain::{
+        error::Result,
+        event::UserEvent,
+        repository::user_repository::UserRepository,
+        user::{User, UserId, Version},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct DeleteUser<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: UserRepository> DeleteUser<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    /// Soft deletes the user. The row stays in place and can be restored.
+    pub async fn run(&self, subject: &Subject, id: &UserId, version: Version) -> Result<User> {
+        self.auth
+            .authorize(subject, Action::DeleteUser(id.clone()))?;
+        self.repo.delete_user(id, version, UserEvent::deleted).await
+    }
+
+    /// Clears the deletion mark. Downstream sees the user come back as an update.
+    pub async fn restore(&self, subject: &Subject, id: &UserId, version: Version) -> Result<User> {
+        self.auth
+            .authorize(subject, Action::DeleteUser(id.clone()))?;
+        self.repo
+            .restore_user(id, version, UserEvent::updated)
+            .await
+    }
+
+    /// Removes the user permanently.
+    pub async fn purge(&self, subject: &Subject, id: &UserId) -> Result<()> {
+        self.auth
+            .authorize(subject, Action::DeleteUser(id.clone()))?;
+        self.repo.purge_user(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::{NaiveDate, Utc};
+    use mockall::predicate::{always, eq};
+
+    use crate::{
+        domain::repository::user_repository::MockUserRepository,
+        usecase::policy::fixtures::{allow, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_delete_user() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_delete_user()
+            .with(eq(UserId(100)), eq(Version(3)), always())
+            .returning(|id, version, raise| {
+                let user = User {
+                    id: id.clone(),
+                    name: "TestName".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
+                    deleted_at: Some(Utc::now()),
+                    version: version.next(),
+                };
+                assert_matches!(raise(&user), UserEvent::UserDeleted(_));
+                Ok(user)
+            });
+        repo.expect_purge_user().never();
+
+        let auth = allow(Action::DeleteUser(UserId(100)));
+        let usecase = DeleteUser::new(&repo, &auth);
+        let user = usecase.run(&subject(), &UserId(100), Version(3)).await?;
+
+        assert_matches!(user, User { id, deleted_at: Some(_), .. } => {
+            assert_eq!(id, UserId(100));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_user() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_purge_user()
+            .with(eq(UserId(100)))
+            .returning(|_| Ok(()));
+        repo.expect_delete_user().never();
+
+        let auth = allow(Action::DeleteUser(UserId(100)));
+        let usecase = DeleteUser::new(&repo, &auth);
+
+        usecase.purge(&subject(), &UserId(100)).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use futures::stream::BoxStream;
+use validator::Validate;
+
+use crate::{
+    domain::{
+        error::Result,
+        repository::user_repository::UserRepository,
+        user::{query::UserQuery, User},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct ExportUsers<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: UserRepository> ExportUsers<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    pub async fn run(
+        &self,
+        subject: &Subject,
+        query: &UserQuery,
+    ) -> Result<BoxStream<'a, Result<User>>> {
+        self.auth.authorize(subject, Action::ListUsers)?;
+        query.validate()?;
+        self.repo.stream_users(query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::NaiveDate;
+    use futures::{stream, StreamExt, TryStreamExt};
+    use mockall::predicate::eq;
+    use pretty_assertions::assert_eq;
+    use validator::ValidationErrors;
+
+    use crate::{
+        domain::{
+            error::DomainError,
+            repository::user_repository::MockUserRepository,
+            user::{UserId, Version},
+        },
+        usecase::policy::fixtures::{allow, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_export_users() -> anyhow::Result<()> {
+        let user = User {
+            id: UserId(100),
+            name: "TestName".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
+            deleted_at: None,
+            version: Version::INITIAL,
+        };
+
+        let mut repo = MockUserRepository::new();
+        let expected = user.clone();
+        repo.expect_stream_users()
+            .with(eq(UserQuery::default()))
+            .returning(move |_| Ok(stream::iter([Ok(expected.clone())]).boxed()));
+
+        let auth = allow(Action::ListUsers);
+        let usecase = ExportUsers::new(&repo, &auth);
+        let res = usecase
+            .run(&subject(), &UserQuery::default())
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(res, vec![user]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_users_if_validation_error() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_stream_users().never();
+
+        let auth = allow(Action::ListUsers);
+        let usecase = ExportUsers::new(&repo, &auth);
+        let res = usecase
+            .run(
+                &subject(),
+                &UserQuery {
+                    name_prefix: Some("".into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map(|_| ());
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name_prefix"));
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    domain::{
+        error::{DomainError, Result},
+        repository::user_repository::UserRepository,
+        user::{Deleted, User, UserId},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct GetUser<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: UserRepository> GetUser<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    pub async fn run(&self, subject: &Subject, id: &UserId, deleted: Deleted) -> Result<User> {
+        self.auth.authorize(subject, Action::ReadUser(id.clone()))?;
+        self.repo
+            .get_user(id, deleted)
+            .await?
+            .ok_or(DomainError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::NaiveDate;
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{repository::user_repository::MockUserRepository, user::Version},
+        usecase::policy::fixtures::{allow, deny, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_user() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_get_user()
+            .with(eq(UserId(100)), eq(Deleted::Exclude))
+            .returning(|id, _| {
+                Ok(Some(User {
+                    id: id.clone(),
+                    name: "TestName".into(),
+                    email: None,
+                    birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
+                    deleted_at: None,
+                    version: Version::INITIAL,
+                }))
+            });
+
+        let auth = allow(Action::ReadUser(UserId(100)));
+        let usecase = GetUser::new(&repo, &auth);
+        let user = usecase
+            .run(&subject(), &UserId(100), Deleted::Exclude)
+            .await?;
+
+        assert_eq!(user.id, UserId(100));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_not_found() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_get_user()
+            .with(eq(UserId(100)), eq(Deleted::Exclude))
+            .returning(|_, _| Ok(None));
+
+        let auth = allow(Action::ReadUser(UserId(100)));
+        let usecase = GetUser::new(&repo, &auth);
+        let res = usecase
+            .run(&subject(), &UserId(100), Deleted::Exclude)
+            .await;
+
+        assert_matches!(res, Err(DomainError::NotFound));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_if_forbidden() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_get_user().never();
+
+        let auth = deny(Action::ReadUser(UserId(100)));
+        let usecase = GetUser::new(&repo, &auth);
+        let res = usecase
+            .run(&subject(), &UserId(100), Deleted::Exclude)
+            .await;
+
+        assert_matches!(res, Err(DomainError::Forbidden));
+
+        Ok(())
+    }
+}
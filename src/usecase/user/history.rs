@@ -0,0 +1,87 @@
+use crate::{
+    domain::{
+        audit::AuditEntry,
+        error::{DomainError, Result},
+        repository::audit_repository::AuditRepository,
+        user::UserId,
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct GetUserHistory<'a, R: AuditRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: AuditRepository> GetUserHistory<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    /// The recorded changes to the user. A user without any is not found, since every user
+    /// has at least its creation recorded.
+    pub async fn run(&self, subject: &Subject, id: &UserId) -> Result<Vec<AuditEntry>> {
+        self.auth
+            .authorize(subject, Action::ReadHistory(id.clone()))?;
+        let entries = self.repo.get_user_history(id).await?;
+        if entries.is_empty() {
+            return Err(DomainError::NotFound);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use mockall::predicate::eq;
+    use serde_json::json;
+
+    use crate::{
+        domain::{
+            audit::{Actor, Operation},
+            repository::audit_repository::MockAuditRepository,
+        },
+        usecase::policy::fixtures::{allow, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_user_history() -> anyhow::Result<()> {
+        let mut repo = MockAuditRepository::new();
+        repo.expect_get_user_history()
+            .with(eq(UserId(100)))
+            .returning(|id| {
+                Ok(vec![AuditEntry {
+                    id: 1,
+                    user_id: id.clone(),
+                    actor: Actor::system(),
+                    operation: Operation::Create,
+                    diff: json!({"before": null, "after": {"name": "name"}}),
+                    created_at: Utc::now(),
+                }])
+            });
+
+        let auth = allow(Action::ReadHistory(UserId(100)));
+        let usecase = GetUserHistory::new(&repo, &auth);
+        let entries = usecase.run(&subject(), &UserId(100)).await?;
+
+        assert_eq!(entries.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_history_not_found() {
+        let mut repo = MockAuditRepository::new();
+        repo.expect_get_user_history().returning(|_| Ok(vec![]));
+
+        let auth = allow(Action::ReadHistory(UserId(100)));
+        let usecase = GetUserHistory::new(&repo, &auth);
+        let res = usecase.run(&subject(), &UserId(100)).await;
+
+        assert_matches!(res, Err(DomainError::NotFound));
+    }
+}
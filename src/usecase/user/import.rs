@@ -0,0 +1,344 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use chrono::NaiveDate;
+use futures::{pin_mut, Stream, StreamExt};
+use validator::ValidateArgs;
+
+use crate::{
+    domain::{
+        clock::Clock,
+        error::Result,
+        event::UserEvent,
+        repository::user_repository::UserRepository,
+        user::{
+            import::{ImportError, ImportReport, ImportRow},
+            normalize_email, NewUser,
+        },
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub const BATCH_SIZE: usize = 500;
+
+/// Creates users from an import file in batches.
+///
+/// Rows keep being validated after the first error so the report lists every bad line, but
+/// nothing more is written. An email address used earlier in the file or by an existing user is
+/// reported on its line rather than left to fail the whole import at insertion. Run it on a repository bound to a transaction and commit only if the
+/// report [`is_ok`](ImportReport::is_ok), so that an import is all or nothing.
+pub struct ImportUsers<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+    clock: &'a dyn Clock,
+    batch_size: usize,
+}
+
+impl<'a, R: UserRepository> ImportUsers<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer, clock: &'a dyn Clock) -> Self {
+        Self {
+            repo,
+            auth,
+            clock,
+            batch_size: BATCH_SIZE,
+        }
+    }
+
+    pub fn with_batch_size(self, batch_size: usize) -> Self {
+        Self { batch_size, ..self }
+    }
+
+    /// With `dry_run`, only validates the rows.
+    pub async fn run(
+        &self,
+        subject: &Subject,
+        rows: impl Stream<Item = ImportRow> + Send,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        self.auth.authorize(subject, Action::CreateUsers)?;
+        pin_mut!(rows);
+        // One day for the whole file, even across midnight.
+        let today = self.clock.today();
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        // The line each address was first seen on.
+        let mut emails = HashMap::new();
+        let mut batch = Vec::with_capacity(self.batch_size);
+        while let Some(row) = rows.next().await {
+            report.rows += 1;
+            let user = row
+                .user
+                .and_then(|x| validate(&x, &today).map(|_| x))
+                .and_then(|x| match x.email.as_deref().map(normalize_email) {
+                    Some(email) => match emails.entry(email) {
+                        Entry::Occupied(e) => Err(format!("email is already on line {}", e.get())),
+                        Entry::Vacant(e) => {
+                            e.insert(row.line);
+                            Ok(x)
+                        }
+                    },
+                    None => Ok(x),
+                });
+            match user {
+                Ok(user) => {
+                    batch.push((row.line, user));
+                    if batch.len() >= self.batch_size {
+                        self.flush(&mut batch, &mut report).await?;
+                    }
+                }
+                Err(message) => report.errors.push(ImportError {
+                    line: row.line,
+                    message,
+                }),
+            }
+        }
+        self.flush(&mut batch, &mut report).await?;
+        report.errors.sort_by_key(|x| x.line);
+        if !report.is_ok() {
+            report.imported = 0;
+        }
+        Ok(report)
+    }
+
+    /// Reports the rows whose address is taken, then creates the users unless something is wrong
+    /// so far.
+    async fn flush(
+        &self,
+        batch: &mut Vec<(u64, NewUser)>,
+        report: &mut ImportReport,
+    ) -> Result<()> {
+        let batch = std::mem::take(batch);
+        let emails = batch
+            .iter()
+            .filter_map(|(_, x)| x.email.as_deref().map(normalize_email))
+            .collect::<Vec<_>>();
+        let taken = if emails.is_empty() {
+            vec![]
+        } else {
+            self.repo.taken_emails(&emails).await?
+        };
+        for (line, user) in &batch {
+            if let Some(email) = &user.email {
+                if taken.contains(&normalize_email(email)) {
+                    report.errors.push(ImportError {
+                        line: *line,
+                        message: "email is already taken".into(),
+                    });
+                }
+            }
+        }
+        if report.dry_run || !report.is_ok() || batch.is_empty() {
+            return Ok(());
+        }
+        let users = self
+            .repo
+            .create_users(
+                batch.into_iter().map(|(_, x)| x).collect(),
+                UserEvent::created,
+            )
+            .await?;
+        report.imported += users.len();
+        Ok(())
+    }
+}
+
+fn validate(user: &NewUser, today: &NaiveDate) -> std::result::Result<(), String> {
+    user.validate_args(today).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use futures::stream;
+    use mockall::{predicate::always, Sequence};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        domain::{
+            clock::FixedClock,
+            repository::user_repository::MockUserRepository,
+            user::{User, UserId, Version},
+        },
+        usecase::policy::fixtures::{allow, subject},
+    };
+
+    use super::*;
+
+    fn clock() -> FixedClock {
+        FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap())
+    }
+
+    fn row(line: u64, name: &str) -> ImportRow {
+        ImportRow {
+            line,
+            user: Ok(NewUser {
+                name: name.into(),
+                email: None,
+                birth_date: NaiveDate::from_ymd_opt(2003, 1, 1),
+            }),
+        }
+    }
+
+    fn created(users: Vec<NewUser>) -> Vec<User> {
+        users
+            .into_iter()
+            .enumerate()
+            .map(|(i, x)| User {
+                id: UserId(i as i64),
+                name: x.name,
+                email: x.email,
+                birth_date: x.birth_date,
+                deleted_at: None,
+                version: Version::INITIAL,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_import_users() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        let mut seq = Sequence::new();
+        for len in [2, 1] {
+            repo.expect_create_users()
+                .withf(move |x, _| x.len() == len)
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|x, _| Ok(created(x)));
+        }
+
+        let report = ImportUsers::new(&repo, &allow(Action::CreateUsers), &clock())
+            .with_batch_size(2)
+            .run(
+                &subject(),
+                stream::iter(vec![row(2, "a"), row(3, "b"), row(4, "c")]),
+                false,
+            )
+            .await?;
+
+        assert_eq!(
+            report,
+            ImportReport {
+                rows: 3,
+                imported: 3,
+                dry_run: false,
+                errors: vec![],
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_users_invalid() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_create_users()
+            .with(always(), always())
+            .times(1)
+            .returning(|x, _| Ok(created(x)));
+
+        let report = ImportUsers::new(&repo, &allow(Action::CreateUsers), &clock())
+            .with_batch_size(1)
+            .run(
+                &subject(),
+                stream::iter(vec![
+                    row(2, "a"),
+                    row(3, ""),
+                    ImportRow {
+                        line: 4,
+                        user: Err("missing field `name`".into()),
+                    },
+                    row(5, "d"),
+                ]),
+                false,
+            )
+            .await?;
+
+        assert_eq!(report.rows, 4);
+        assert_eq!(report.imported, 0);
+        assert_eq!(
+            report.errors.iter().map(|x| x.line).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert_eq!(report.errors[1].message, "missing field `name`");
+
+        Ok(())
+    }
+
+    fn with_email(line: u64, email: &str) -> ImportRow {
+        let mut row = row(line, "name");
+        if let Ok(user) = &mut row.user {
+            user.email = Some(email.into());
+        }
+        row
+    }
+
+    #[tokio::test]
+    async fn test_import_users_duplicate_email() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_taken_emails().returning(|x| {
+            Ok(x.iter()
+                .filter(|x| *x == "old@example.com")
+                .cloned()
+                .collect())
+        });
+        repo.expect_create_users().never();
+
+        let report = ImportUsers::new(&repo, &allow(Action::CreateUsers), &clock())
+            .with_batch_size(2)
+            .run(
+                &subject(),
+                stream::iter(vec![
+                    with_email(2, "a@example.com"),
+                    with_email(3, "old@example.com"),
+                    with_email(4, "b@example.com"),
+                    with_email(5, "A@example.com"),
+                ]),
+                false,
+            )
+            .await?;
+
+        assert_eq!(report.imported, 0);
+        assert_eq!(
+            report.errors,
+            vec![
+                ImportError {
+                    line: 3,
+                    message: "email is already taken".into(),
+                },
+                ImportError {
+                    line: 5,
+                    message: "email is already on line 2".into(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_users_dry_run() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_create_users().never();
+
+        let report = ImportUsers::new(&repo, &allow(Action::CreateUsers), &clock())
+            .run(
+                &subject(),
+                stream::iter(vec![row(2, "a"), row(3, "b")]),
+                true,
+            )
+            .await?;
+
+        assert_eq!(
+            report,
+            ImportReport {
+                rows: 2,
+                imported: 0,
+                dry_run: true,
+                errors: vec![],
+            }
+        );
+
+        Ok(())
+    }
+}
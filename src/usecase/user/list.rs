@@ -0,0 +1,123 @@
+use validator::Validate;
+
+use crate::{
+    domain::{
+        error::Result,
+        pagination::{Page, Pagination},
+        repository::user_repository::UserRepository,
+        user::{query::UserQuery, User},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct ListUsers<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: UserRepository> ListUsers<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    pub async fn run(
+        &self,
+        subject: &Subject,
+        query: &UserQuery,
+        page: &Pagination,
+    ) -> Result<Page<User>> {
+        self.auth.authorize(subject, Action::ListUsers)?;
+        query.validate()?;
+        page.validate()?;
+        self.repo.get_users(query, page).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mockall::predicate::eq;
+    use validator::ValidationErrors;
+
+    use crate::{
+        domain::{error::DomainError, repository::user_repository::MockUserRepository},
+        usecase::policy::fixtures::{allow, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_users() -> anyhow::Result<()> {
+        let page = Pagination::default();
+
+        let mut repo = MockUserRepository::new();
+        repo.expect_get_users()
+            .with(eq(UserQuery::default()), eq(page.clone()))
+            .returning(|_, _| {
+                Ok(Page {
+                    items: vec![],
+                    next_cursor: None,
+                })
+            });
+
+        let auth = allow(Action::ListUsers);
+        let usecase = ListUsers::new(&repo, &auth);
+        let res = usecase
+            .run(&subject(), &UserQuery::default(), &page)
+            .await?;
+
+        assert!(res.items.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_if_validation_error() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_get_users().never();
+
+        let auth = allow(Action::ListUsers);
+        let usecase = ListUsers::new(&repo, &auth);
+        let res = usecase
+            .run(
+                &subject(),
+                &UserQuery::default(),
+                &Pagination {
+                    limit: 0,
+                    cursor: None,
+                },
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "limit"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_if_query_validation_error() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_get_users().never();
+
+        let auth = allow(Action::ListUsers);
+        let usecase = ListUsers::new(&repo, &auth);
+        let res = usecase
+            .run(
+                &subject(),
+                &UserQuery {
+                    name_prefix: Some("".into()),
+                    ..Default::default()
+                },
+                &Pagination::default(),
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name_prefix"));
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    domain::{
+        error::{DomainError, Result},
+        repository::role_repository::RoleRepository,
+        role::Role,
+        user::UserId,
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct ManageRoles<'a, R: RoleRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: RoleRepository> ManageRoles<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    /// Roles are part of the user, so whoever may read the user may read them.
+    pub async fn list(&self, subject: &Subject, id: &UserId) -> Result<Vec<Role>> {
+        self.auth.authorize(subject, Action::ReadUser(id.clone()))?;
+        self.repo.find_roles(id).await?.ok_or(DomainError::NotFound)
+    }
+
+    pub async fn grant(&self, subject: &Subject, id: &UserId, role: Role) -> Result<()> {
+        self.auth.authorize(subject, Action::ManageRoles)?;
+        self.repo.grant_role(id, role).await
+    }
+
+    pub async fn revoke(&self, subject: &Subject, id: &UserId, role: Role) -> Result<()> {
+        self.auth.authorize(subject, Action::ManageRoles)?;
+        self.repo.revoke_role(id, role).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::repository::role_repository::MockRoleRepository,
+        usecase::policy::fixtures::{allow, deny, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_grant_role() -> anyhow::Result<()> {
+        let mut repo = MockRoleRepository::new();
+        repo.expect_grant_role()
+            .with(eq(UserId(100)), eq(Role::Viewer))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let auth = allow(Action::ManageRoles);
+        ManageRoles::new(&repo, &auth)
+            .grant(&subject(), &UserId(100), Role::Viewer)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revoke_role_if_forbidden() -> anyhow::Result<()> {
+        let mut repo = MockRoleRepository::new();
+        repo.expect_revoke_role().never();
+
+        let auth = deny(Action::ManageRoles);
+        let res = ManageRoles::new(&repo, &auth)
+            .revoke(&subject(), &UserId(100), Role::Admin)
+            .await;
+
+        assert_matches!(res, Err(DomainError::Forbidden));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use validator::Validate;
+
+use crate::{
+    domain::{
+        error::Result,
+        repository::user_repository::UserRepository,
+        user::search::{SearchHit, UserSearch},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct SearchUsers<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+}
+
+impl<'a, R: UserRepository> SearchUsers<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer) -> Self {
+        Self { repo, auth }
+    }
+
+    pub async fn run(&self, subject: &Subject, search: &UserSearch) -> Result<Vec<SearchHit>> {
+        self.auth.authorize(subject, Action::ListUsers)?;
+        search.validate()?;
+        self.repo.search_users(search).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::NaiveDate;
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{
+            error::DomainError,
+            repository::user_repository::MockUserRepository,
+            user::{User, UserId, Version},
+        },
+        usecase::policy::fixtures::{allow, subject},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_users() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_search_users()
+            .with(eq(UserSearch::new("name")))
+            .returning(|_| {
+                Ok(vec![SearchHit {
+                    user: User {
+                        id: UserId(100),
+                        name: "name".into(),
+                        email: None,
+                        birth_date: NaiveDate::from_ymd_opt(1923, 1, 1),
+                        deleted_at: None,
+                        version: Version::INITIAL,
+                    },
+                    score: 1.0,
+                }])
+            });
+
+        let auth = allow(Action::ListUsers);
+        let usecase = SearchUsers::new(&repo, &auth);
+        let hits = usecase.run(&subject(), &UserSearch::new("name")).await?;
+
+        assert_eq!(hits.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_users_if_validation_error() {
+        let mut repo = MockUserRepository::new();
+        repo.expect_search_users().never();
+
+        let auth = allow(Action::ListUsers);
+        let usecase = SearchUsers::new(&repo, &auth);
+        let res = usecase.run(&subject(), &UserSearch::new("")).await;
+
+        assert_matches!(res, Err(DomainError::Validation(_)));
+    }
+}
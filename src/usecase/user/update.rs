@@ -0,0 +1,232 @@
+use validator::ValidateArgs;
+
+use crate::{
+    domain::{
+        clock::Clock,
+        error::Result,
+        event::UserEvent,
+        repository::user_repository::UserRepository,
+        user::{User, UserId, UserPatch, UserUpdate, Version},
+    },
+    usecase::policy::{Action, Authorizer, Subject},
+};
+
+pub struct UpdateUser<'a, R: UserRepository> {
+    repo: &'a R,
+    auth: &'a dyn Authorizer,
+    clock: &'a dyn Clock,
+}
+
+impl<'a, R: UserRepository> UpdateUser<'a, R> {
+    pub fn new(repo: &'a R, auth: &'a dyn Authorizer, clock: &'a dyn Clock) -> Self {
+        Self { repo, auth, clock }
+    }
+
+    pub async fn run(
+        &self,
+        subject: &Subject,
+        id: &UserId,
+        version: Version,
+        user: UserUpdate,
+    ) -> Result<User> {
+        self.auth
+            .authorize(subject, Action::UpdateUser(id.clone()))?;
+        user.validate_args(&self.clock.today())?;
+        self.repo
+            .update_user(id, version, user, UserEvent::updated)
+            .await
+    }
+
+    pub async fn patch(
+        &self,
+        subject: &Subject,
+        id: &UserId,
+        version: Version,
+        patch: UserPatch,
+    ) -> Result<User> {
+        self.auth
+            .authorize(subject, Action::UpdateUser(id.clone()))?;
+        patch.validate_args(&self.clock.today())?;
+        self.repo
+            .patch_user(id, version, patch, UserEvent::updated)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::NaiveDate;
+    use mockall::predicate::{always, eq};
+    use validator::ValidationErrors;
+
+    use crate::{
+        domain::{
+            clock::FixedClock, error::DomainError, repository::user_repository::MockUserRepository,
+        },
+        usecase::policy::fixtures::{allow, deny, subject},
+    };
+
+    use super::*;
+
+    fn clock() -> FixedClock {
+        FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_update_user() -> anyhow::Result<()> {
+        let update = UserUpdate {
+            name: "TestName".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
+        };
+
+        let mut repo = MockUserRepository::new();
+        repo.expect_update_user()
+            .with(
+                eq(UserId(100)),
+                eq(Version(3)),
+                eq(update.clone()),
+                always(),
+            )
+            .returning(|id, version, x, raise| {
+                let user = User {
+                    id: id.clone(),
+                    name: x.name,
+                    email: x.email,
+                    birth_date: x.birth_date,
+                    deleted_at: None,
+                    version: version.next(),
+                };
+                assert_matches!(raise(&user), UserEvent::UserUpdated(_));
+                Ok(user)
+            });
+
+        let auth = allow(Action::UpdateUser(UserId(100)));
+        let clock = clock();
+        let usecase = UpdateUser::new(&repo, &auth, &clock);
+        let user = usecase
+            .run(&subject(), &UserId(100), Version(3), update)
+            .await?;
+
+        assert_matches!(user, User { id, name, birth_date, .. } => {
+            assert_eq!(id, UserId(100));
+            assert_eq!(name, "TestName");
+            assert_eq!(birth_date, NaiveDate::from_ymd_opt(1924, 1, 1));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user_if_validation_error() -> anyhow::Result<()> {
+        let update = UserUpdate {
+            name: "".into(),
+            email: None,
+            birth_date: NaiveDate::from_ymd_opt(1924, 1, 1),
+        };
+
+        let mut repo = MockUserRepository::new();
+        repo.expect_update_user().never();
+
+        let auth = allow(Action::UpdateUser(UserId(100)));
+        let clock = clock();
+        let usecase = UpdateUser::new(&repo, &auth, &clock);
+        let res = usecase
+            .run(&subject(), &UserId(100), Version(3), update)
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_user() -> anyhow::Result<()> {
+        let patch = UserPatch {
+            birth_date: Some(NaiveDate::from_ymd_opt(2003, 1, 1)),
+            ..Default::default()
+        };
+
+        let mut repo = MockUserRepository::new();
+        repo.expect_patch_user()
+            .with(eq(UserId(100)), eq(Version(3)), eq(patch.clone()), always())
+            .returning(|id, version, x, raise| {
+                let user = User {
+                    id: id.clone(),
+                    name: "TestName".into(),
+                    email: None,
+                    birth_date: x.birth_date.unwrap(),
+                    deleted_at: None,
+                    version: version.next(),
+                };
+                assert_matches!(raise(&user), UserEvent::UserUpdated(_));
+                Ok(user)
+            });
+
+        let auth = allow(Action::UpdateUser(UserId(100)));
+        let clock = clock();
+        let usecase = UpdateUser::new(&repo, &auth, &clock);
+        let user = usecase
+            .patch(&subject(), &UserId(100), Version(3), patch)
+            .await?;
+
+        assert_matches!(user, User { birth_date, .. } => {
+            assert_eq!(birth_date, NaiveDate::from_ymd_opt(2003, 1, 1));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_user_if_validation_error() -> anyhow::Result<()> {
+        let patch = UserPatch {
+            name: Some("".into()),
+            ..Default::default()
+        };
+
+        let mut repo = MockUserRepository::new();
+        repo.expect_patch_user().never();
+
+        let auth = allow(Action::UpdateUser(UserId(100)));
+        let clock = clock();
+        let usecase = UpdateUser::new(&repo, &auth, &clock);
+        let res = usecase
+            .patch(&subject(), &UserId(100), Version(3), patch)
+            .await;
+
+        assert_matches!(res, Err(DomainError::Validation(e)) => {
+            assert!(ValidationErrors::has_error(&Err(e), "name"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_user_if_forbidden() -> anyhow::Result<()> {
+        let mut repo = MockUserRepository::new();
+        repo.expect_update_user().never();
+
+        let auth = deny(Action::UpdateUser(UserId(100)));
+        let clock = clock();
+        let usecase = UpdateUser::new(&repo, &auth, &clock);
+        let res = usecase
+            .run(
+                &subject(),
+                &UserId(100),
+                Version(3),
+                UserUpdate {
+                    name: "TestName".into(),
+                    email: None,
+                    birth_date: None,
+                },
+            )
+            .await;
+
+        assert_matches!(res, Err(DomainError::Forbidden));
+
+        Ok(())
+    }
+}
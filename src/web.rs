@@ -1,22 +1,91 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::extract::FromRef;
 use sea_orm::DatabaseConnection;
 
-use crate::infrastructure::repository::rdb::create_connection;
+use crate::{
+    config::CONFIG,
+    domain::{
+        clock::Clock,
+        credential::{PasswordHasher, TokenGenerator},
+    },
+    infrastructure::{
+        clock::SystemClock,
+        event::log::LogPublisher,
+        password::Argon2Hasher,
+        repository::rdb::{create_connection, outbox::OutboxRelay},
+        token::RandomTokens,
+    },
+    interface::public_id::PublicIds,
+    usecase::policy::{Authorizer, Policy},
+};
+
+use self::auth::{Authenticator, TokenAuthenticator};
 
 #[derive(Debug, Clone, FromRef)]
 pub struct AppState {
     pub db_conn: DatabaseConnection,
+    pub public_ids: PublicIds,
+    pub clock: Arc<dyn Clock>,
+    pub password_hasher: Arc<dyn PasswordHasher>,
+    pub token_generator: Arc<dyn TokenGenerator>,
+    pub authorizer: Arc<dyn Authorizer>,
+    pub authenticator: Arc<dyn Authenticator>,
+}
+
+impl AppState {
+    /// Loads the JWT key, so call it before serving rather than per request. Fails if the key
+    /// cannot be loaded.
+    pub fn new(db_conn: DatabaseConnection) -> anyhow::Result<Self> {
+        let public_ids = PublicIds::new(&CONFIG.public_id_secret);
+        let authenticator =
+            TokenAuthenticator::from_config(&CONFIG, public_ids.clone(), db_conn.clone())?;
+        Ok(Self {
+            db_conn,
+            public_ids,
+            clock: Arc::new(SystemClock),
+            password_hasher: Arc::new(Argon2Hasher::new(CONFIG.argon2_params.clone())),
+            token_generator: Arc::new(RandomTokens),
+            authorizer: Arc::new(Policy),
+            authenticator: Arc::new(authenticator),
+        })
+    }
+
+    /// Computes ages on the day `clock` says it is rather than today.
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Tells whom bearer tokens belong to with `authenticator` instead.
+    pub fn with_authenticator(self, authenticator: impl Authenticator + 'static) -> Self {
+        Self {
+            authenticator: Arc::new(authenticator),
+            ..self
+        }
+    }
 }
 
 pub mod api;
+pub mod auth;
+pub mod error;
+pub mod extract;
+pub mod problem;
 
 pub async fn serve() -> anyhow::Result<()> {
     let db_conn = create_connection().await?;
 
+    let relay_conn = db_conn.clone();
+    tokio::spawn(async move {
+        OutboxRelay::new(&relay_conn, LogPublisher)
+            .run(Duration::from_secs(1))
+            .await
+    });
+
     axum::Server::bind(&SocketAddr::from(([0, 0, 0, 0], 3000)))
-        .serve(api::api(AppState { db_conn }).await?.into_make_service())
+        .serve(api::api(AppState::new(db_conn)?).await?.into_make_service())
         .await?;
     Ok(())
 }
@@ -1,78 +1,619 @@
-use std::net::SocketAddr;
+use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing, Json, Router as AxumRouter,
+    body::StreamBody,
+    extract::{DefaultBodyLimit, OriginalUri, State},
+    http::{header, Request, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing, Router as AxumRouter,
 };
-use sea_orm::DatabaseConnection;
+use chrono::NaiveDate;
+use futures::{stream, StreamExt};
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncWriteExt, sync::mpsc};
 
 use crate::{
-    domain::{repository::user_repository::UserRepository, user::UserId},
-    infrastructure::repository::rdb::{create_connection, RdbRepository},
-    interface::controller::users,
+    config::CONFIG,
+    domain::{
+        audit::Actor,
+        clock::{Clock, FixedClock},
+        credential::{Credentials, NewPassword, PasswordHasher, TokenGenerator},
+        error::DomainError,
+        pagination::{Cursor, Pagination, DEFAULT_LIMIT},
+        user::{
+            query::{Sort, UserQuery},
+            search::UserSearch,
+            Deleted, NewUser, User, UserPatch, UserUpdate,
+        },
+    },
+    infrastructure::{
+        import::{stream_users, Format},
+        repository::rdb::RdbRepository,
+    },
+    interface::{
+        controller::{auth, users},
+        cursor,
+        export::{encode, ExportFormat},
+        presenter::{AuditEntryView, PageView, SearchHitView, SessionView, UserView},
+        public_id::PublicIds,
+    },
+    usecase::policy::{Authorizer, Subject},
 };
 
-use super::AppState;
+use super::{
+    auth::{authenticate, authorize_users, AuthUser, Authenticator},
+    extract::{etag, IfMatch, Json, Multipart, Query, UserPath, ValidatedJson},
+    problem::{self, Problem},
+    AppState,
+};
 
 type Router = AxumRouter<AppState>;
 
-pub async fn serve() -> anyhow::Result<()> {
-    let db_conn = create_connection().await?;
+pub async fn api(state: AppState) -> anyhow::Result<AxumRouter> {
+    let routes = Router::new()
+        .nest("/api", v1(state.authenticator.clone()))
+        .with_state(state);
+    // Layers of a router run after it has routed, so the rewrite wraps the routes from outside.
+    Ok(AxumRouter::new()
+        .fallback_service(routes)
+        .layer(middleware::from_fn(custom_methods))
+        .layer(middleware::from_fn(problem::render)))
+}
+
+/// Custom methods such as `/users:import` and the path their route has.
+const CUSTOM_METHODS: [(&str, &str); 1] = [("/api/v1/users:import", "/api/v1/users/import")];
 
-    axum::Server::bind(&SocketAddr::from(([0, 0, 0, 0], 3000)))
-        .serve(api(AppState { db_conn }).await?.into_make_service())
-        .await?;
-    Ok(())
+/// The router has no escape for `:`, so requests for a custom method are routed by the path in
+/// [`CUSTOM_METHODS`]. Problems still name the path that was requested.
+async fn custom_methods<B>(mut req: Request<B>, next: Next<B>) -> Response {
+    let route = CUSTOM_METHODS
+        .iter()
+        .find(|(path, _)| *path == req.uri().path())
+        .map(|(_, route)| *route);
+    if let Some(route) = route {
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("{}?{}", route, query),
+            None => route.to_owned(),
+        };
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = Some(path_and_query.parse().expect("route is a valid path"));
+        *req.uri_mut() = Uri::from_parts(parts).expect("only the path changed");
+    }
+    next.run(req).await
 }
 
-pub async fn api(state: AppState) -> anyhow::Result<AxumRouter> {
-    Ok(Router::new().nest("/api", v1()).with_state(state))
+fn v1(authenticator: Arc<dyn Authenticator>) -> Router {
+    Router::new().nest(
+        "/v1",
+        public_routes().merge(
+            v1_routes()
+                .route_layer(middleware::from_fn(authorize_users))
+                .route_layer(middleware::from_fn_with_state(authenticator, authenticate)),
+        ),
+    )
 }
 
-fn v1() -> Router {
-    Router::new().nest("/v1", v1_routes())
+/// Routes that need no bearer token.
+fn public_routes() -> Router {
+    Router::new().route("/auth/login", routing::post(login))
 }
 
+/// Largest upload `/users:import` accepts. Well above the default limit, since the file is
+/// streamed rather than buffered.
+const IMPORT_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// How much of an upload may be read ahead of the parser.
+const IMPORT_PIPE_SIZE: usize = 64 * 1024;
+
 fn v1_routes() -> Router {
     Router::new()
-        .route("/users", routing::get(get_users))
-        .route("/users/:id", routing::get(get_user))
+        .route("/users", routing::get(get_users).post(create_user))
+        .route("/users/search", routing::get(search_users))
+        .route("/users/export", routing::get(export_users))
+        // Requested as `/users:import`, see `custom_methods`.
+        .route(
+            "/users/import",
+            routing::post(import_users)
+                .fallback(import_users_method_not_allowed)
+                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .route(
+            "/users/:id",
+            routing::get(get_user)
+                .put(update_user)
+                .patch(patch_user)
+                .delete(delete_user),
+        )
+        .route("/users/:id/restore", routing::post(restore_user))
+        .route("/users/:id/history", routing::get(get_user_history))
+        .route("/users/:id/password", routing::put(set_password))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DeletedParams {
+    #[serde(default)]
+    deleted: Deleted,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ListParams {
+    #[serde(default)]
+    deleted: Deleted,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_age: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_age: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<String>,
+}
+
+impl ListParams {
+    fn query(&self) -> Result<UserQuery, DomainError> {
+        Ok(UserQuery {
+            deleted: self.deleted,
+            name_prefix: self.name_prefix.clone(),
+            min_age: self.min_age,
+            max_age: self.max_age,
+            sort: self
+                .sort
+                .as_deref()
+                .map(Sort::parse_list)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+
+    fn pagination(&self, ids: &PublicIds) -> Result<Pagination, DomainError> {
+        Ok(Pagination {
+            limit: self.limit.unwrap_or(DEFAULT_LIMIT),
+            cursor: self
+                .cursor
+                .as_deref()
+                .map(|x| cursor::decode(x, ids))
+                .transpose()?,
+        })
+    }
+
+    /// RFC 8288 `Link` header value pointing at the first and, if any, next page.
+    fn links(&self, path: &str, next: Option<&Cursor>, ids: &PublicIds) -> String {
+        let link = |cursor: Option<&Cursor>, rel: &str| {
+            let params = ListParams {
+                cursor: cursor.map(|x| cursor::encode(x, ids)),
+                ..self.clone()
+            };
+            format!(
+                "<{}?{}>; rel=\"{}\"",
+                path,
+                serde_urlencoded::to_string(params).unwrap_or_default(),
+                rel
+            )
+        };
+        std::iter::once(link(None, "first"))
+            .chain(next.map(|x| link(Some(x), "next")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+async fn get_users(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
+) -> Result<impl IntoResponse, DomainError> {
+    // One day for both the age filter and the ages shown, even across midnight.
+    let today = clock.today();
+    let repo = RdbRepository::new(&conn).with_clock(Arc::new(FixedClock(today)));
+    let page = users::get_users(
+        &repo,
+        auth.as_ref(),
+        &subject,
+        &params.query()?,
+        &params.pagination(&ids)?,
+    )
+    .await?;
+    let links = params.links(uri.path(), page.next_cursor.as_ref(), &ids);
+    Ok((
+        StatusCode::OK,
+        [(header::LINK, links)],
+        Json(PageView::new(page, &ids, today)),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchParams {
+    q: String,
+    limit: Option<u64>,
 }
 
-async fn get_users(State(conn): State<DatabaseConnection>) -> impl IntoResponse {
+#[derive(Debug, Serialize)]
+struct SearchResults {
+    items: Vec<SearchHitView>,
+}
+
+async fn search_users(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    Query(params): Query<SearchParams>,
+) -> Result<impl IntoResponse, DomainError> {
     let repo = RdbRepository::new(&conn);
-    users::get_users(&repo)
-        .await
-        .map(|users| (StatusCode::OK, Json(users)))
-        .map_err(internal_error)
+    let search = UserSearch {
+        limit: params.limit.unwrap_or(DEFAULT_LIMIT),
+        ..UserSearch::new(params.q)
+    };
+    let items = users::search_users(&repo, auth.as_ref(), &subject, &search)
+        .await?
+        .into_iter()
+        .map(|x| SearchHitView::new(x, &ids, clock.today()))
+        .collect();
+    Ok((StatusCode::OK, Json(SearchResults { items })))
 }
 
 async fn get_user(
     State(conn): State<DatabaseConnection>,
-    Path(user_id): Path<i64>,
-) -> impl IntoResponse {
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    UserPath(id): UserPath,
+    Query(params): Query<DeletedParams>,
+) -> Result<impl IntoResponse, DomainError> {
     let repo = RdbRepository::new(&conn);
-    repo.get_user(&UserId(user_id))
+    users::get_user(&repo, auth.as_ref(), &subject, &id, params.deleted)
+        .await
+        .map(|x| with_etag(x, &ids, clock.today()))
+}
+
+async fn create_user(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    actor: Actor,
+    ValidatedJson(user): ValidatedJson<NewUser>,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn)
+        .with_actor(actor)
+        .with_clock(clock.clone());
+    let user = users::create_user(&repo, auth.as_ref(), clock.as_ref(), &subject, user).await?;
+    Ok((
+        StatusCode::CREATED,
+        [(
+            header::LOCATION,
+            format!("/api/v1/users/{}", ids.encode(&user.id)),
+        )],
+        with_etag(user, &ids, clock.today()),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportParams {
+    format: ExportFormat,
+}
+
+/// Streams every user in `format`. The rows are read on a task of their own since the response
+/// body must outlive the handler; an error after the first chunk can only abort the body.
+async fn export_users(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    Query(params): Query<ExportParams>,
+) -> Result<impl IntoResponse, DomainError> {
+    let (tx, mut rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let today = clock.today();
+        let repo = RdbRepository::new(&conn).with_clock(Arc::new(FixedClock(today)));
+        let mut chunks = match users::export_users(
+            &repo,
+            auth.as_ref(),
+            &subject,
+            &UserQuery::default(),
+        )
+        .await
+        {
+            Ok(users) => encode(params.format, users, ids, today),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        };
+        while let Some(chunk) = chunks.next().await {
+            let failed = chunk.is_err();
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let first = rx.recv().await.transpose()?;
+    let rest = stream::unfold(rx, |mut rx| async { rx.recv().await.map(|x| (x, rx)) });
+    Ok((
+        [(header::CONTENT_TYPE, params.format.content_type())],
+        StreamBody::new(stream::iter(first.map(Ok)).chain(rest)),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImportParams {
+    format: Format,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Imports the `file` field of a multipart upload as it arrives. If any line is invalid, nothing is
+/// imported and the problem lists the invalid lines.
+#[allow(clippy::too_many_arguments)]
+async fn import_users(
+    State(conn): State<DatabaseConnection>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    actor: Actor,
+    Query(params): Query<ImportParams>,
+    Multipart(mut multipart): Multipart,
+) -> Result<impl IntoResponse, Problem> {
+    let mut file = loop {
+        match multipart.next_field().await? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => {}
+            None => {
+                return Err(
+                    Problem::new(StatusCode::BAD_REQUEST).with_detail("file field is required")
+                )
+            }
+        }
+    };
+
+    // The field borrows the request, so it is piped to the parser as it arrives.
+    let (mut writer, reader) = tokio::io::duplex(IMPORT_PIPE_SIZE);
+    let upload = async move {
+        while let Some(chunk) = file.chunk().await? {
+            // The parser is gone, so nobody wants the rest.
+            if writer.write_all(&chunk).await.is_err() {
+                break;
+            }
+        }
+        Ok::<_, Problem>(())
+    };
+    let tx = conn.begin().await.map_err(DomainError::from)?;
+    let repo = RdbRepository::new(&tx)
+        .with_actor(actor)
+        .with_clock(clock.clone());
+    let import = async {
+        Ok::<_, Problem>(
+            users::import_users(
+                &repo,
+                auth.as_ref(),
+                clock.as_ref(),
+                &subject,
+                stream_users(params.format, reader),
+                params.dry_run,
+            )
+            .await?,
+        )
+    };
+    let ((), report) = tokio::try_join!(upload, import)?;
+    if !report.is_ok() {
+        return Err(Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .with_type("/problems/import-error", "Import failed")
+            .with_detail(format!(
+                "{} of {} lines are invalid",
+                report.errors.len(),
+                report.rows
+            ))
+            .with_lines(report.errors));
+    }
+    tx.commit().await.map_err(DomainError::from)?;
+    Ok((StatusCode::OK, Json(report)))
+}
+
+/// Other methods on `/users:import`. The router answers them with a `405` but no `Allow`.
+async fn import_users_method_not_allowed() -> impl IntoResponse {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, "POST")])
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn update_user(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    actor: Actor,
+    UserPath(id): UserPath,
+    IfMatch(version): IfMatch,
+    ValidatedJson(user): ValidatedJson<UserUpdate>,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn)
+        .with_actor(actor)
+        .with_clock(clock.clone());
+    users::update_user(
+        &repo,
+        auth.as_ref(),
+        clock.as_ref(),
+        &subject,
+        &id,
+        version,
+        user,
+    )
+    .await
+    .map(|x| with_etag(x, &ids, clock.today()))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn patch_user(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    actor: Actor,
+    UserPath(id): UserPath,
+    IfMatch(version): IfMatch,
+    ValidatedJson(patch): ValidatedJson<UserPatch>,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn)
+        .with_actor(actor)
+        .with_clock(clock.clone());
+    users::patch_user(
+        &repo,
+        auth.as_ref(),
+        clock.as_ref(),
+        &subject,
+        &id,
+        version,
+        patch,
+    )
+    .await
+    .map(|x| with_etag(x, &ids, clock.today()))
+}
+
+async fn delete_user(
+    State(conn): State<DatabaseConnection>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    actor: Actor,
+    UserPath(id): UserPath,
+    IfMatch(version): IfMatch,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn).with_actor(actor);
+    users::delete_user(&repo, auth.as_ref(), &subject, &id, version)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn restore_user(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    actor: Actor,
+    UserPath(id): UserPath,
+    IfMatch(version): IfMatch,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn).with_actor(actor);
+    users::restore_user(&repo, auth.as_ref(), &subject, &id, version)
         .await
-        .map(|x| (StatusCode::OK, Json(x)))
-        .map_err(internal_error)
+        .map(|x| with_etag(x, &ids, clock.today()))
+}
+
+#[derive(Debug, Serialize)]
+struct History {
+    items: Vec<AuditEntryView>,
+}
+
+async fn get_user_history(
+    State(conn): State<DatabaseConnection>,
+    State(ids): State<PublicIds>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    subject: Subject,
+    UserPath(id): UserPath,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn);
+    let items = users::get_user_history(&repo, auth.as_ref(), &subject, &id)
+        .await?
+        .into_iter()
+        .map(|x| AuditEntryView::new(x, &ids))
+        .collect();
+    Ok((StatusCode::OK, Json(History { items })))
+}
+
+async fn login(
+    State(conn): State<DatabaseConnection>,
+    State(hasher): State<Arc<dyn PasswordHasher>>,
+    State(tokens): State<Arc<dyn TokenGenerator>>,
+    Json(credentials): Json<Credentials>,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn);
+    let session = auth::login(
+        &repo,
+        hasher.as_ref(),
+        tokens.as_ref(),
+        CONFIG.session_ttl,
+        credentials,
+    )
+    .await?;
+    Ok((
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, "no-store")],
+        Json(SessionView::new(session)),
+    ))
+}
+
+/// Sets or replaces the user's password, checked against the configured policy. Only users can,
+/// so API keys are forbidden before the usecase is asked.
+async fn set_password(
+    State(conn): State<DatabaseConnection>,
+    State(hasher): State<Arc<dyn PasswordHasher>>,
+    State(auth): State<Arc<dyn Authorizer>>,
+    user: AuthUser,
+    UserPath(id): UserPath,
+    Json(password): Json<NewPassword>,
+) -> Result<impl IntoResponse, DomainError> {
+    let repo = RdbRepository::new(&conn);
+    auth::set_password(
+        &repo,
+        hasher.as_ref(),
+        &CONFIG.password_policy,
+        auth.as_ref(),
+        &user.subject(),
+        &id,
+        password,
+    )
+    .await
+    .map(|_| StatusCode::NO_CONTENT)
 }
 
-fn internal_error(err: anyhow::Error) -> (StatusCode, String) {
-    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+/// The user as the body, with its version as `ETag` so it can be sent back in `If-Match`.
+fn with_etag(user: User, ids: &PublicIds, today: NaiveDate) -> impl IntoResponse {
+    (
+        [(header::ETAG, etag(user.version))],
+        Json(UserView::new(user, ids, today)),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        domain::{
+            api_key::{ApiKey, ApiKeySecret, NewApiKey, Scope},
+            clock::FixedClock,
+            credential::SessionToken,
+            repository::{api_key_repository::ApiKeyRepository, role_repository::RoleRepository},
+            role::Role,
+            user::UserId,
+        },
         fixture,
-        infrastructure::repository::rdb::{
-            entity::{self, users},
-            fixtures,
+        infrastructure::{
+            repository::rdb::{
+                create_connection,
+                entity::{self, users},
+                fixtures,
+            },
+            token::RandomTokens,
         },
+        web::auth::fixtures::{bearer, ClientAuthenticator, CLIENT_KEY},
     };
     use anyhow::Context;
     use assert_json_diff::assert_json_include;
@@ -80,8 +621,9 @@ mod tests {
         body::Body,
         http::{Request, StatusCode},
     };
+    use chrono::{NaiveDate, Utc};
     use pretty_assertions::assert_eq;
-    use sea_orm::{ActiveModelTrait, EntityTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter};
     use serde_json::json;
     use tower::ServiceExt;
 
@@ -96,12 +638,29 @@ mod tests {
     async fn connection() -> anyhow::Result<(DatabaseConnection, AppState)> {
         let conn = create_connection().await?;
 
-        let state = AppState {
-            db_conn: conn.clone(),
-        };
+        let state = AppState::new(conn.clone())?;
+        let client = ClientAuthenticator(state.authenticator.clone());
+        let state = state
+            .with_authenticator(client)
+            .with_clock(FixedClock(NaiveDate::from_ymd_opt(2023, 6, 15).unwrap()));
         Ok((conn, state))
     }
 
+    /// A request from a client whose key may read and write users. Unlike a user with a role, the
+    /// key leaves the users table to the tests.
+    fn request() -> axum::http::request::Builder {
+        Request::builder().header("authorization", format!("Bearer {}", CLIENT_KEY))
+    }
+
+    /// A request authenticated as the user.
+    fn request_as(id: i64) -> axum::http::request::Builder {
+        Request::builder().header("authorization", bearer(&UserId(id)))
+    }
+
+    fn public_id(id: i64) -> String {
+        PublicIds::new(&CONFIG.public_id_secret).encode(&UserId(id))
+    }
+
     async fn fixture_user(conn: &DatabaseConnection) -> anyhow::Result<users::ActiveModel> {
         Ok(fixture!(
             conn,
@@ -124,7 +683,7 @@ mod tests {
             let app = api(state).await?;
             Ok(app
                 .oneshot(
-                    Request::builder()
+                    request()
                         .method(axum::http::Method::GET)
                         .uri("/api/v1/users")
                         .body(Body::empty())?,
@@ -143,13 +702,251 @@ mod tests {
         assert_json_include!(
             actual: body,
             expected:
-                json!([
-                    {
-                        "name": "name",
-                        "age": 100,
-                    },
-                ]),
+                json!({
+                    "items": [
+                        {
+                            "name": "name",
+                            "age": 100,
+                        },
+                    ],
+                    "next_cursor": null,
+                }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_users_paginated() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let y = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let first = app
+                .clone()
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri("/api/v1/users?limit=1")
+                        .body(Body::empty())?,
+                )
+                .await?;
+            let link = first.headers()["link"].to_str()?.to_owned();
+            let first = parse_json!(first);
+            let next = link
+                .split(", ")
+                .find_map(|x| x.strip_suffix("; rel=\"next\""))
+                .and_then(|x| x.strip_prefix('<')?.strip_suffix('>'))
+                .context("next link")?
+                .to_owned();
+            let second = app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri(&next)
+                        .body(Body::empty())?,
+                )
+                .await?;
+            Ok((first, next, parse_json!(second)))
+        }
+        .await;
+
+        x.clone().delete(&conn).await?;
+        y.clone().delete(&conn).await?;
+        let (first, next, second) = res?;
+
+        assert_eq!(first["items"][0]["id"], json!(public_id(x.id.unwrap())));
+        assert_eq!(
+            next,
+            format!(
+                "/api/v1/users?deleted=exclude&limit=1&cursor={}",
+                first["next_cursor"].as_str().context("next_cursor")?
+            )
         );
+        assert_eq!(second["items"][0]["id"], json!(public_id(y.id.unwrap())));
+        assert_eq!(second["next_cursor"], json!(null));
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_users_filtered() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let y = users::ActiveModel {
+            name: sea_orm::ActiveValue::Set("other".into()),
+            birth_date: sea_orm::ActiveValue::Set(NaiveDate::from_ymd_opt(2003, 1, 1)),
+            ..fixtures::user()
+        }
+        .save(&conn)
+        .await
+        .context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri("/api/v1/users?name_prefix=na&max_age=100&sort=-age,name")
+                        .body(Body::empty())?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.clone().delete(&conn).await?;
+        y.delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = parse_json!(res);
+
+        assert_eq!(body["items"].as_array().map(Vec::len), Some(1));
+        assert_eq!(body["items"][0]["id"], json!(public_id(x.id.unwrap())));
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_search_users() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri("/api/v1/users/search?q=name")
+                        .body(Body::empty())?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.clone().delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = parse_json!(res);
+
+        assert_eq!(body["items"][0]["id"], json!(public_id(x.id.unwrap())));
+        assert_eq!(body["items"][0]["name"], json!("name"));
+        assert_eq!(body["items"][0]["score"], json!(1.0));
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("csv", "text/csv; charset=utf-8")]
+    #[case("ndjson", "application/x-ndjson")]
+    #[case("json", "application/json")]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_export_users(
+        #[case] format: &str,
+        #[case] content_type: &str,
+    ) -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let res = app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri(format!("/api/v1/users/export?format={}", format))
+                        .body(Body::empty())?,
+                )
+                .await?;
+            let status = res.status();
+            let headers = res.headers().clone();
+            let body = String::from_utf8(hyper::body::to_bytes(res.into_body()).await?.to_vec())?;
+            Ok((status, headers, body))
+        }
+        .await;
+
+        x.clone().delete(&conn).await?;
+        let (status, headers, body) = res?;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], content_type);
+        let id = public_id(x.id.unwrap());
+        let row = match format {
+            "csv" => format!("\n{},name,,1923-01-01,100,,1\n", id),
+            _ => format!(
+                "{{\"id\":\"{}\",\"name\":\"name\",\"email\":null,\"birth_date\":\"1923-01-01\",\"age\":100,\"deleted_at\":null,\"version\":1}}",
+                id
+            ),
+        };
+        assert!(body.contains(&row), "{} not in {}", row, body);
+        if format == "json" {
+            serde_json::from_str::<Vec<serde_json::Value>>(&body)?;
+        }
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("/api/v1/users?unknown=1", StatusCode::BAD_REQUEST)]
+    #[case("/api/v1/users?sort=email", StatusCode::UNPROCESSABLE_ENTITY)]
+    #[case(
+        "/api/v1/users?min_age=30&max_age=20",
+        StatusCode::UNPROCESSABLE_ENTITY
+    )]
+    #[case("/api/v1/users/search", StatusCode::BAD_REQUEST)]
+    #[case("/api/v1/users/search?q=", StatusCode::UNPROCESSABLE_ENTITY)]
+    #[case("/api/v1/users/export", StatusCode::BAD_REQUEST)]
+    #[case("/api/v1/users/export?format=xml", StatusCode::BAD_REQUEST)]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_users_invalid_query(
+        #[case] uri: &str,
+        #[case] status: StatusCode,
+    ) -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), status);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_users_invalid_cursor() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::GET)
+                    .uri("/api/v1/users?cursor=invalid")
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(parse_json!(res)["errors"]["cursor"].is_array());
         Ok(())
     }
 
@@ -164,9 +961,12 @@ mod tests {
             let app = api(state).await?;
             Ok(app
                 .oneshot(
-                    Request::builder()
+                    request()
                         .method(axum::http::Method::GET)
-                        .uri(format!("/api/v1/users/{}", x.id.clone().unwrap()))
+                        .uri(format!(
+                            "/api/v1/users/{}",
+                            public_id(x.id.clone().unwrap())
+                        ))
                         .body(Body::empty())?,
                 )
                 .await?)
@@ -180,6 +980,7 @@ mod tests {
         let res = res?;
 
         assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["etag"], "\"1\"");
 
         let body = parse_json!(res);
 
@@ -187,7 +988,40 @@ mod tests {
             actual: body,
             expected: json!({
                 "name": x.name.unwrap(),
-                "age": x.age.unwrap(),
+                "birth_date": x.birth_date.unwrap(),
+                "age": 100,
+                "version": 1,
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_user_404() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::GET)
+                    .uri(format!("/api/v1/users/{}", public_id(0)))
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "title": "Not Found",
+                "status": 404,
+                "instance": format!("/api/v1/users/{}", public_id(0)),
             }),
         );
         Ok(())
@@ -195,14 +1029,14 @@ mod tests {
 
     #[serial_test::serial]
     #[tokio::test]
-    async fn test_api_v1_get_user_400() -> anyhow::Result<()> {
+    async fn test_api_v1_get_user_invalid_public_id() -> anyhow::Result<()> {
         let (_, state) = connection().await?;
 
         let res: anyhow::Result<_> = async {
             let app = api(state).await?;
             Ok(app
                 .oneshot(
-                    Request::builder()
+                    request()
                         .method(axum::http::Method::GET)
                         .uri("/api/v1/users/id")
                         .body(Body::empty())?,
@@ -213,7 +1047,1358 @@ mod tests {
 
         let res = res?;
 
-        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "instance": "/api/v1/users/id",
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_user_invalid_json() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::PUT)
+                    .uri(format!("/api/v1/users/{}", public_id(0)))
+                    .header("if-match", "\"1\"")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({"name": "name", "birth_date": "twenty"}).to_string(),
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        assert!(parse_json!(res)["detail"].is_string());
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_unknown_route() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::GET)
+                    .uri("/api/v1/unknown")
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        assert_eq!(parse_json!(res)["instance"], json!("/api/v1/unknown"));
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_method_not_allowed() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri(format!("/api/v1/users/{}", public_id(1)))
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        assert_eq!(parse_json!(res)["status"], json!(405));
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_post_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri("/api/v1/users")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({
+                            "name": "name",
+                            "email": "Name@Example.com",
+                            "birth_date": "2003-01-01",
+                        })
+                        .to_string(),
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let location = res.headers()["location"].to_str()?.to_owned();
+        let body = parse_json!(res);
+        let id = body["id"].as_str().context("id")?;
+        let user_id = PublicIds::new(&CONFIG.public_id_secret)
+            .decode(id)
+            .context("decode id")?;
+
+        entity::prelude::Users::delete_by_id(user_id.0)
+            .exec(&conn)
+            .await?;
+
+        assert_eq!(location, format!("/api/v1/users/{}", id));
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "name": "name",
+                "email": "name@example.com",
+                "birth_date": "2003-01-01",
+                "age": 20,
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_post_user_409() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = users::ActiveModel {
+            email: sea_orm::ActiveValue::Set(Some("name@example.com".into())),
+            ..fixtures::user()
+        }
+        .save(&conn)
+        .await
+        .context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::POST)
+                        .uri("/api/v1/users")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({"name": "name", "email": "NAME@example.com"}).to_string(),
+                        ))?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "status": 409,
+                "errors": {
+                    "email": [{"code": "unique"}],
+                },
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_post_user_422() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri("/api/v1/users")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({"name": "", "birth_date": "2003-01-01"}).to_string(),
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "errors": {
+                    "name": [{"code": "length"}],
+                },
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_post_user_422_before_authorization() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let id = x.id.clone().unwrap();
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    // A user without roles, who may not create users.
+                    request_as(id)
+                        .method(axum::http::Method::POST)
+                        .uri("/api/v1/users")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            // The day after the state's clock.
+                            json!({"name": "", "birth_date": "2023-06-16"}).to_string(),
+                        ))?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_json_include!(
+            actual: parse_json!(res),
+            expected: json!({
+                "errors": {
+                    "name": [{"code": "length"}],
+                    "birth_date": [{"code": "birth_date"}],
+                },
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::PUT)
+                        .uri(format!(
+                            "/api/v1/users/{}",
+                            public_id(x.id.clone().unwrap())
+                        ))
+                        .header("if-match", "\"1\"")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({"name": "new name", "birth_date": "2003-01-01"}).to_string(),
+                        ))?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.clone().delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["etag"], "\"2\"");
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "id": public_id(x.id.unwrap()),
+                "name": "new name",
+                "birth_date": "2003-01-01",
+                "age": 20,
+                "version": 2,
+            }),
+        );
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(None, StatusCode::PRECONDITION_REQUIRED)]
+    #[case(Some("\"2\""), StatusCode::PRECONDITION_FAILED)]
+    #[case(Some("W/\"1\""), StatusCode::PRECONDITION_FAILED)]
+    #[case(Some("*"), StatusCode::PRECONDITION_FAILED)]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_user_precondition(
+        #[case] if_match: Option<&str>,
+        #[case] status: StatusCode,
+    ) -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let mut req = request()
+                .method(axum::http::Method::PUT)
+                .uri(format!(
+                    "/api/v1/users/{}",
+                    public_id(x.id.clone().unwrap())
+                ))
+                .header("content-type", "application/json");
+            if let Some(if_match) = if_match {
+                req = req.header("if-match", if_match);
+            }
+            Ok(app
+                .oneshot(req.body(Body::from(
+                    json!({"name": "new name", "birth_date": "2003-01-01"}).to_string(),
+                ))?)
+                .await?)
+        }
+        .await;
+
+        let stored = entity::prelude::Users::find_by_id(x.id.clone().unwrap())
+            .one(&conn)
+            .await?;
+        x.delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), status);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        assert_eq!(
+            stored.map(|x| (x.name, x.version)),
+            Some(("name".into(), 1))
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_user_404() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::PUT)
+                    .uri(format!("/api/v1/users/{}", public_id(0)))
+                    .header("if-match", "\"1\"")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({"name": "new name", "birth_date": "2003-01-01"}).to_string(),
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(parse_json!(res)["status"], json!(404));
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_patch_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::PATCH)
+                        .uri(format!(
+                            "/api/v1/users/{}",
+                            public_id(x.id.clone().unwrap())
+                        ))
+                        .header("if-match", "\"1\"")
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({"birth_date": "2003-01-01"}).to_string()))?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.clone().delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "id": public_id(x.id.unwrap()),
+                "name": x.name.unwrap(),
+                "birth_date": "2003-01-01",
+                "age": 20,
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_patch_user_422() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::PATCH)
+                        .uri(format!(
+                            "/api/v1/users/{}",
+                            public_id(x.id.clone().unwrap())
+                        ))
+                        .header("if-match", "\"1\"")
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({"name": ""}).to_string()))?,
+                )
+                .await?)
+        }
+        .await;
+
+        x.delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = parse_json!(res);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "type": "/problems/validation-error",
+                "status": 422,
+                "errors": {
+                    "name": [{"code": "length"}],
+                },
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_user_history() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let uri = format!("/api/v1/users/{}", public_id(x.id.clone().unwrap()));
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let before = app
+                .clone()
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri(format!("{}/history", uri))
+                        .body(Body::empty())?,
+                )
+                .await?;
+            app.clone()
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::PATCH)
+                        .uri(&uri)
+                        .header("if-match", "\"1\"")
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({"birth_date": "2003-01-01"}).to_string()))?,
+                )
+                .await?;
+            let after = app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri(format!("{}/history", uri))
+                        .body(Body::empty())?,
+                )
+                .await?;
+            Ok((before, after))
+        }
+        .await;
+
+        entity::prelude::UserAuditLog::delete_many()
+            .filter(entity::user_audit_log::Column::UserId.eq(x.id.clone().unwrap()))
+            .exec(&conn)
+            .await?;
+        x.clone().delete(&conn).await?;
+        let (before, after) = res?;
+
+        assert_eq!(before.status(), StatusCode::NOT_FOUND);
+        assert_eq!(after.status(), StatusCode::OK);
+
+        let body = parse_json!(after);
+
+        assert_json_include!(
+            actual: body,
+            expected: json!({
+                "items": [{
+                    "user_id": public_id(x.id.unwrap()),
+                    "actor": format!("api-key:{}", ApiKeySecret(CLIENT_KEY.into()).prefix()),
+                    "operation": "update",
+                    "diff": {
+                        "before": {"birth_date": "1923-01-01", "version": 1},
+                        "after": {"birth_date": "2003-01-01", "version": 2},
+                    },
+                }],
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_get_user_history_of_created_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .clone()
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri("/api/v1/users")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({"name": "name"}).to_string()))?,
+            )
+            .await?;
+        let id = parse_json!(res)["id"].as_str().context("id")?.to_owned();
+        let user_id = PublicIds::new(&CONFIG.public_id_secret)
+            .decode(&id)
+            .context("decode id")?;
+
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::GET)
+                    .uri(format!("/api/v1/users/{}/history", id))
+                    .body(Body::empty())?,
+            )
+            .await;
+
+        entity::prelude::UserAuditLog::delete_many()
+            .filter(entity::user_audit_log::Column::UserId.eq(user_id.0))
+            .exec(&conn)
+            .await?;
+        entity::prelude::Users::delete_by_id(user_id.0)
+            .exec(&conn)
+            .await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = parse_json!(res);
+
+        assert_eq!(body["items"][0]["user_id"], json!(id));
+        assert_eq!(body["items"][0]["operation"], json!("create"));
+        assert_eq!(
+            body["items"][0]["diff"]["after"]
+                .as_object()
+                .context("after")?
+                .get("id"),
+            None
+        );
+        Ok(())
+    }
+
+    fn multipart(file: &str) -> Body {
+        Body::from(format!(
+            "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"users.csv\"\r\n\
+             \r\n\
+             {}\r\n\
+             --BOUNDARY--\r\n",
+            file
+        ))
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_import_users() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::POST)
+                        .uri("/api/v1/users:import?format=csv")
+                        .header("content-type", "multipart/form-data; boundary=BOUNDARY")
+                        .body(multipart(
+                            "name,birth_date\nimported-1,2003-01-01\nimported-2,1993-01-01",
+                        ))?,
+                )
+                .await?)
+        }
+        .await;
+
+        let imported = entity::prelude::Users::find()
+            .filter(users::Column::Name.starts_with("imported-"))
+            .all(&conn)
+            .await?;
+        let ids = imported.iter().map(|x| x.id).collect::<Vec<_>>();
+        entity::prelude::UserAuditLog::delete_many()
+            .filter(entity::user_audit_log::Column::UserId.is_in(ids.clone()))
+            .exec(&conn)
+            .await?;
+        entity::prelude::Users::delete_many()
+            .filter(users::Column::Id.is_in(ids))
+            .exec(&conn)
+            .await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            parse_json!(res),
+            json!({"rows": 2, "imported": 2, "dry_run": false, "errors": []})
+        );
+        assert_eq!(
+            imported
+                .into_iter()
+                .map(|x| (x.name, x.birth_date))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "imported-1".to_string(),
+                    NaiveDate::from_ymd_opt(2003, 1, 1)
+                ),
+                (
+                    "imported-2".to_string(),
+                    NaiveDate::from_ymd_opt(1993, 1, 1)
+                )
+            ]
+        );
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(
+        "format=csv&dry_run=true",
+        StatusCode::OK,
+        json!({"rows": 2, "imported": 0, "dry_run": true, "errors": []})
+    )]
+    #[case(
+        "format=ndjson",
+        StatusCode::UNPROCESSABLE_ENTITY,
+        json!({
+            "type": "/problems/import-error",
+            "title": "Import failed",
+            "status": 422,
+            "detail": "3 of 3 lines are invalid",
+            "instance": "/api/v1/users:import",
+            "lines": [
+                {"line": 1},
+                {"line": 2},
+                {"line": 3},
+            ],
+        })
+    )]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_import_users_not_imported(
+        #[case] query: &str,
+        #[case] status: StatusCode,
+        #[case] expected: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri(format!("/api/v1/users:import?{}", query))
+                    .header("content-type", "multipart/form-data; boundary=BOUNDARY")
+                    .body(multipart(
+                        "name,birth_date\nimported-1,2003-01-01\nimported-2,1993-01-01",
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), status);
+        assert_json_include!(actual: parse_json!(res), expected: expected);
+        assert_eq!(
+            entity::prelude::Users::find()
+                .filter(users::Column::Name.starts_with("imported-"))
+                .all(&conn)
+                .await?,
+            vec![]
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_import_users_duplicate_email() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri("/api/v1/users:import?format=csv")
+                    .header("content-type", "multipart/form-data; boundary=BOUNDARY")
+                    .body(multipart(
+                        "name,email\n\
+                         imported-1,twice@example.com\n\
+                         imported-2,other@example.com\n\
+                         imported-3,Twice@example.com",
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_json_include!(
+            actual: parse_json!(res),
+            expected: json!({
+                "detail": "1 of 3 lines are invalid",
+                "lines": [{"line": 4, "message": "email is already on line 2"}],
+            })
+        );
+        assert_eq!(
+            entity::prelude::Users::find()
+                .filter(users::Column::Name.starts_with("imported-"))
+                .all(&conn)
+                .await?,
+            vec![]
+        );
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("/api/v1/users:import", StatusCode::BAD_REQUEST)]
+    #[case("/api/v1/users:export?format=csv", StatusCode::NOT_FOUND)]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_import_users_invalid(
+        #[case] uri: &str,
+        #[case] status: StatusCode,
+    ) -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri(uri)
+                    .header("content-type", "multipart/form-data; boundary=BOUNDARY")
+                    .body(multipart("name,birth_date"))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), status);
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(
+        axum::http::Method::GET,
+        "/api/v1/users:import",
+        StatusCode::METHOD_NOT_ALLOWED
+    )]
+    #[case(axum::http::Method::GET, "/api/v1/usersX", StatusCode::NOT_FOUND)]
+    #[case(
+        axum::http::Method::DELETE,
+        "/api/v1/users:export",
+        StatusCode::NOT_FOUND
+    )]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_import_users_method(
+        #[case] method: axum::http::Method,
+        #[case] uri: &str,
+        #[case] status: StatusCode,
+    ) -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(request().method(method).uri(uri).body(Body::empty())?)
+            .await?;
+
+        assert_eq!(res.status(), status);
+        if status == StatusCode::METHOD_NOT_ALLOWED {
+            assert_eq!(res.headers()["allow"], "POST");
+        }
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_import_users_too_large() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::POST)
+                    .uri("/api/v1/users:import?format=csv&dry_run=true")
+                    .header("content-type", "multipart/form-data; boundary=BOUNDARY")
+                    .body(multipart(&format!(
+                        "name\n{}",
+                        "a".repeat(IMPORT_BODY_LIMIT)
+                    )))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_delete_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let uri = format!("/api/v1/users/{}", public_id(x.id.clone().unwrap()));
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let deleted = app
+                .clone()
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::DELETE)
+                        .uri(&uri)
+                        .header("if-match", "\"1\"")
+                        .body(Body::empty())?,
+                )
+                .await?;
+            let users = app
+                .clone()
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri("/api/v1/users")
+                        .body(Body::empty())?,
+                )
+                .await?;
+            let with_deleted = app
+                .oneshot(
+                    request()
+                        .method(axum::http::Method::GET)
+                        .uri(format!("{}?deleted=include", uri))
+                        .body(Body::empty())?,
+                )
+                .await?;
+            Ok((deleted, users, with_deleted))
+        }
+        .await;
+
+        x.delete(&conn).await?;
+        let (deleted, users, with_deleted) = res?;
+
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+        assert_eq!(users.status(), StatusCode::OK);
+        assert_eq!(parse_json!(users)["items"], json!([]));
+        assert_eq!(with_deleted.status(), StatusCode::OK);
+
+        let body = parse_json!(with_deleted);
+        assert!(body["deleted_at"].is_string());
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_delete_user_404() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::DELETE)
+                    .uri(format!("/api/v1/users/{}", public_id(0)))
+                    .header("if-match", "\"1\"")
+                    .body(Body::empty())?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(parse_json!(res)["status"], json!(404));
+        Ok(())
+    }
+
+    /// Sets the user's password as the user.
+    async fn put_password(
+        app: &AxumRouter,
+        id: i64,
+        password: &str,
+    ) -> anyhow::Result<axum::response::Response> {
+        Ok(app
+            .clone()
+            .oneshot(
+                request_as(id)
+                    .method(axum::http::Method::PUT)
+                    .uri(format!("/api/v1/users/{}/password", public_id(id)))
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "password": password }).to_string()))?,
+            )
+            .await?)
+    }
+
+    async fn post_login(
+        app: &AxumRouter,
+        email: &str,
+        password: &str,
+    ) -> anyhow::Result<axum::response::Response> {
+        Ok(app
+            .clone()
+            .oneshot(
+                // Public, so no token.
+                Request::builder()
+                    .method(axum::http::Method::POST)
+                    .uri("/api/v1/auth/login")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({ "email": email, "password": password }).to_string(),
+                    ))?,
+            )
+            .await?)
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_login() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = users::ActiveModel {
+            email: sea_orm::ActiveValue::Set(Some("login@example.com".into())),
+            ..fixtures::user()
+        }
+        .save(&conn)
+        .await
+        .context("create user")?;
+        let id = x.id.clone().unwrap();
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let set = put_password(&app, id, "correct horse battery").await?;
+            let login = post_login(&app, "Login@Example.com", "correct horse battery").await?;
+            let wrong = post_login(&app, "login@example.com", "wrong horse battery").await?;
+            let unknown = post_login(&app, "nobody@example.com", "correct horse battery").await?;
+            Ok((set, login, wrong, unknown))
+        }
+        .await;
+
+        let sessions = entity::user_sessions::Entity::find()
+            .filter(entity::user_sessions::Column::UserId.eq(id))
+            .all(&conn)
+            .await?;
+        x.delete(&conn).await?;
+        let (set, login, wrong, unknown) = res?;
+
+        assert_eq!(set.status(), StatusCode::NO_CONTENT);
+
+        assert_eq!(login.status(), StatusCode::OK);
+        assert_eq!(login.headers()["cache-control"], "no-store");
+        let body = parse_json!(login);
+        assert_eq!(body["token_type"], json!("Bearer"));
+        let token = SessionToken(body["token"].as_str().context("token")?.into());
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].token_hash, token.hash(&RandomTokens));
+
+        for res in [wrong, unknown] {
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+            assert_eq!(parse_json!(res)["detail"], json!("invalid credentials"));
+        }
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_login_then_get_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = users::ActiveModel {
+            email: sea_orm::ActiveValue::Set(Some("login@example.com".into())),
+            ..fixtures::user()
+        }
+        .save(&conn)
+        .await
+        .context("create user")?;
+        let id = x.id.clone().unwrap();
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            put_password(&app, id, "correct horse battery").await?;
+            let login = post_login(&app, "login@example.com", "correct horse battery").await?;
+            let token = parse_json!(login)["token"]
+                .as_str()
+                .context("token")?
+                .to_owned();
+            let get = |token: String| {
+                Request::builder()
+                    .method(axum::http::Method::GET)
+                    .uri(format!("/api/v1/users/{}", public_id(id)))
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+            };
+            let own = app.clone().oneshot(get(token.clone())?).await?;
+            let unknown = app
+                .clone()
+                .oneshot(get(SessionToken::generate(&RandomTokens).0)?)
+                .await?;
+            put_password(&app, id, "another horse battery").await?;
+            let revoked = app.oneshot(get(token)?).await?;
+            Ok((own, unknown, revoked))
+        }
+        .await;
+
+        x.delete(&conn).await?;
+        let (own, unknown, revoked) = res?;
+
+        assert_eq!(own.status(), StatusCode::OK);
+        assert_eq!(parse_json!(own)["id"], json!(public_id(id)));
+        assert_eq!(unknown.status(), StatusCode::UNAUTHORIZED);
+        // Changing the password signs the user out.
+        assert_eq!(revoked.status(), StatusCode::UNAUTHORIZED);
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_password_422() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let id = x.id.clone().unwrap();
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            put_password(&app, id, "short").await
+        }
+        .await;
+
+        x.delete(&conn).await?;
+        let res = res?;
+
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_json_include!(
+            actual: parse_json!(res),
+            expected: json!({
+                "errors": {
+                    "password": [{"code": "too_short", "params": {"min": 12}}],
+                },
+            }),
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_password_404() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let admin = fixture_user(&conn).await.context("create user")?;
+        let admin_id = admin.id.clone().unwrap();
+        RdbRepository::new(&conn)
+            .grant_role(&UserId(admin_id), Role::Admin)
+            .await?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            Ok(app
+                .oneshot(
+                    request_as(admin_id)
+                        .method(axum::http::Method::PUT)
+                        .uri(format!("/api/v1/users/{}/password", public_id(0)))
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({ "password": "correct horse battery" }).to_string(),
+                        ))?,
+                )
+                .await?)
+        }
+        .await;
+
+        admin.delete(&conn).await?;
+
+        assert_eq!(res?.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_put_password_as_api_key() -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let app = api(state).await?;
+        let res = app
+            .oneshot(
+                request()
+                    .method(axum::http::Method::PUT)
+                    .uri(format!("/api/v1/users/{}/password", public_id(0)))
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({ "password": "correct horse battery" }).to_string(),
+                    ))?,
+            )
+            .await?;
+
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            parse_json!(res)["detail"],
+            json!("only users can do this, not API keys")
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_401_for_deleted_user() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let x = fixture_user(&conn).await.context("create user")?;
+        let id = x.id.clone().unwrap();
+        users::ActiveModel {
+            id: ActiveValue::Unchanged(id),
+            deleted_at: ActiveValue::Set(Some(Utc::now().into())),
+            ..Default::default()
+        }
+        .update(&conn)
+        .await?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let send = |id: i64| {
+                app.clone().oneshot(
+                    request_as(id)
+                        .method(axum::http::Method::GET)
+                        .uri(format!("/api/v1/users/{}", public_id(id)))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            };
+            Ok([send(id).await?, send(0).await?])
+        }
+        .await;
+
+        x.delete(&conn).await?;
+
+        for res in res? {
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                res.headers()["www-authenticate"],
+                r#"Bearer error="invalid_token""#
+            );
+        }
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(None, "Bearer")]
+    #[case(Some("Bearer not-a-token"), r#"Bearer error="invalid_token""#)]
+    #[case(Some("Basic dXNlcjpwYXNz"), "Bearer")]
+    #[case(Some("bearer not-a-token"), r#"Bearer error="invalid_token""#)]
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_401(
+        #[case] authorization: Option<&str>,
+        #[case] challenge: &str,
+    ) -> anyhow::Result<()> {
+        let (_, state) = connection().await?;
+
+        let mut req = Request::builder()
+            .method(axum::http::Method::GET)
+            .uri("/api/v1/users");
+        if let Some(x) = authorization {
+            req = req.header("authorization", x);
+        }
+        let app = api(state).await?;
+        let res = app.oneshot(req.body(Body::empty())?).await?;
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(res.headers()["www-authenticate"], challenge);
+        assert_eq!(res.headers()["content-type"], problem::CONTENT_TYPE);
+        assert_eq!(parse_json!(res)["instance"], json!("/api/v1/users"));
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_api_key() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let repo = RdbRepository::new(&conn);
+        let read_only = NewApiKey {
+            owner: "test".into(),
+            scopes: vec![Scope::UsersRead],
+            expires_at: None,
+        };
+        let secret = ApiKeySecret::generate(&RandomTokens);
+        let key = repo.create_api_key(read_only.clone(), &secret).await?;
+        let revoked_secret = ApiKeySecret::generate(&RandomTokens);
+        let revoked = repo.create_api_key(read_only, &revoked_secret).await?;
+        repo.revoke_api_key(&revoked.id).await?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let send = |method, secret: &ApiKeySecret| {
+                Request::builder()
+                    .method(method)
+                    .uri("/api/v1/users")
+                    .header("authorization", format!("Bearer {}", secret.0))
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({"name": "name"}).to_string()))
+            };
+            let read = app
+                .clone()
+                .oneshot(send(axum::http::Method::GET, &secret)?)
+                .await?;
+            let write = app
+                .clone()
+                .oneshot(send(axum::http::Method::POST, &secret)?)
+                .await?;
+            let revoked = app
+                .oneshot(send(axum::http::Method::GET, &revoked_secret)?)
+                .await?;
+            Ok((read, write, revoked))
+        }
+        .await;
+
+        let used = repo.find_api_key(&secret).await?;
+        entity::prelude::ApiKeys::delete_many()
+            .filter(entity::api_keys::Column::Id.is_in([key.id.0, revoked.id.0]))
+            .exec(&conn)
+            .await?;
+        let (read, write, revoked) = res?;
+
+        assert_eq!(read.status(), StatusCode::OK);
+        assert_matches::assert_matches!(
+            used,
+            Some(ApiKey {
+                last_used_at: Some(_),
+                ..
+            })
+        );
+
+        assert_eq!(write.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            write.headers()["www-authenticate"],
+            r#"Bearer error="insufficient_scope", scope="users:write""#
+        );
+        assert_eq!(write.headers()["content-type"], problem::CONTENT_TYPE);
+
+        assert_eq!(revoked.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            revoked.headers()["www-authenticate"],
+            r#"Bearer error="invalid_token""#
+        );
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_api_v1_roles() -> anyhow::Result<()> {
+        let (conn, state) = connection().await?;
+
+        let viewer = fixture_user(&conn).await.context("create user")?;
+        let viewer_id = viewer.id.clone().unwrap();
+        let user = fixture_user(&conn).await.context("create user")?;
+        let user_id = user.id.clone().unwrap();
+        RdbRepository::new(&conn)
+            .grant_role(&UserId(viewer_id), Role::Viewer)
+            .await?;
+
+        let res: anyhow::Result<_> = async {
+            let app = api(state).await?;
+            let send = |as_id: i64, method, uri: String| {
+                app.clone().oneshot(
+                    request_as(as_id)
+                        .method(method)
+                        .uri(uri)
+                        .header("if-match", "\"1\"")
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({"name": "renamed"}).to_string()))
+                        .unwrap(),
+                )
+            };
+            let user_uri = format!("/api/v1/users/{}", public_id(user_id));
+            let viewer_uri = format!("/api/v1/users/{}", public_id(viewer_id));
+            use axum::http::Method;
+            Ok([
+                send(user_id, Method::GET, user_uri.clone()).await?,
+                send(user_id, Method::PATCH, user_uri.clone()).await?,
+                send(user_id, Method::GET, viewer_uri.clone()).await?,
+                send(user_id, Method::GET, "/api/v1/users".into()).await?,
+                send(viewer_id, Method::GET, "/api/v1/users".into()).await?,
+                send(viewer_id, Method::GET, user_uri.clone()).await?,
+                send(viewer_id, Method::DELETE, user_uri).await?,
+            ])
+        }
+        .await;
+
+        entity::prelude::UserAuditLog::delete_many()
+            .filter(entity::user_audit_log::Column::UserId.eq(user_id))
+            .exec(&conn)
+            .await?;
+        viewer.delete(&conn).await?;
+        user.delete(&conn).await?;
+
+        assert_eq!(
+            res?.map(|x| x.status()),
+            [
+                // Users may read and edit themselves,
+                StatusCode::OK,
+                StatusCode::OK,
+                // but not others without a role.
+                StatusCode::FORBIDDEN,
+                StatusCode::FORBIDDEN,
+                // Viewers may read anyone,
+                StatusCode::OK,
+                StatusCode::OK,
+                // but change no one.
+                StatusCode::FORBIDDEN,
+            ]
+        );
         Ok(())
     }
 }
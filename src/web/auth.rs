@@ -0,0 +1,637 @@
+//! Bearer token authentication. Tokens are either JWTs signed by an issuer we trust, whose
+//! subject is the public id of a user, session tokens from signing in, or API keys for machine
+//! clients.
+
+use std::{
+    fmt::{self, Debug},
+    fs,
+    sync::Arc,
+};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    domain::{
+        api_key::{ApiKey, ApiKeySecret, Scope},
+        audit::Actor,
+        credential::{Session, SessionToken},
+        error::DomainError,
+        role::Role,
+        user::UserId,
+    },
+    infrastructure::repository::rdb::RdbRepository,
+    interface::{
+        controller::{api_keys, auth},
+        public_id::PublicIds,
+    },
+    usecase::policy::Subject,
+};
+
+use super::problem::Problem;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// Public id of the user.
+    pub sub: String,
+    /// Expiry as seconds since the epoch. Tokens without one are rejected.
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+}
+
+/// The user a request was authenticated as.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthUser {
+    pub id: UserId,
+    /// As in the JWT, or what a session amounts to if the user signed in here.
+    pub claims: Claims,
+    /// As stored when the request came in, not as claimed by the token.
+    pub roles: Vec<Role>,
+}
+
+impl AuthUser {
+    /// Who usecases authorize.
+    pub fn subject(&self) -> Subject {
+        Subject::User {
+            id: self.id.clone(),
+            roles: self.roles.clone(),
+        }
+    }
+}
+
+/// Whoever a request was authenticated as.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Principal {
+    User(AuthUser),
+    ApiKey(ApiKey),
+}
+
+impl Principal {
+    /// Users may do anything a key may; keys only what their scopes allow.
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        match self {
+            Principal::User(_) => true,
+            Principal::ApiKey(key) => key.has_scope(scope),
+        }
+    }
+
+    /// Who changes are attributed to in the audit log.
+    pub fn actor(&self) -> Actor {
+        match self {
+            Principal::User(user) => Actor(user.claims.sub.clone()),
+            Principal::ApiKey(key) => Actor(format!("api-key:{}", key.prefix)),
+        }
+    }
+
+    /// Who usecases authorize.
+    pub fn subject(&self) -> Subject {
+        match self {
+            Principal::User(user) => user.subject(),
+            Principal::ApiKey(key) => Subject::ApiKey {
+                scopes: key.scopes.clone(),
+            },
+        }
+    }
+}
+
+/// Tells whom a bearer token belongs to.
+#[async_trait]
+pub trait Authenticator: Debug + Send + Sync {
+    async fn authenticate(&self, token: &str) -> Result<Principal, AuthError>;
+}
+
+/// Checks bearer tokens against the configured JWT key and the stored sessions and API keys.
+pub struct TokenAuthenticator {
+    key: DecodingKey,
+    validation: Validation,
+    ids: PublicIds,
+    conn: DatabaseConnection,
+}
+
+impl Debug for TokenAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenAuthenticator")
+            .field("validation", &self.validation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenAuthenticator {
+    /// Reads the key from `jwt_key_file`. Trailing whitespace of an HS256 secret is not part of
+    /// it, so the file may end with a newline.
+    ///
+    /// The file is read with blocking IO, so this is meant to run once at startup, as
+    /// [`AppState::new`](super::AppState::new) does, and never while serving a request.
+    pub fn from_config(
+        config: &Config,
+        ids: PublicIds,
+        conn: DatabaseConnection,
+    ) -> anyhow::Result<Self> {
+        let path = &config.jwt_key_file;
+        let bytes = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let key = match config.jwt_algorithm {
+            Algorithm::HS256 => DecodingKey::from_secret(trim_end(&bytes)),
+            Algorithm::RS256 => DecodingKey::from_rsa_pem(&bytes)
+                .with_context(|| format!("parse {}", path.display()))?,
+            x => bail!("unsupported JWT algorithm {:?}, use HS256 or RS256", x),
+        };
+        let mut validation = Validation::new(config.jwt_algorithm);
+        if let Some(iss) = &config.jwt_issuer {
+            validation.set_issuer(&[iss]);
+        }
+        if let Some(aud) = &config.jwt_audience {
+            validation.set_audience(&[aud]);
+        }
+        Ok(Self {
+            key,
+            validation,
+            ids,
+            conn,
+        })
+    }
+
+    async fn verify_session(
+        &self,
+        repo: &RdbRepository<'_, DatabaseConnection>,
+        token: &str,
+    ) -> Result<AuthUser, AuthError> {
+        let session = match auth::authenticate_session(repo, &SessionToken(token.into())).await {
+            Ok(x) => x,
+            Err(DomainError::InvalidCredentials) => {
+                return Err(AuthError::InvalidToken(
+                    "session is unknown or expired".into(),
+                ))
+            }
+            Err(e) => return Err(AuthError::Failed(e)),
+        };
+        Ok(AuthUser {
+            claims: self.session_claims(&session),
+            id: session.user_id,
+            roles: vec![],
+        })
+    }
+
+    fn session_claims(&self, session: &Session) -> Claims {
+        Claims {
+            sub: self.ids.encode(&session.user_id),
+            exp: u64::try_from(session.expires_at.timestamp()).unwrap_or_default(),
+            iat: None,
+        }
+    }
+
+    fn verify_jwt(&self, token: &str) -> Result<AuthUser, AuthError> {
+        let claims = jsonwebtoken::decode::<Claims>(token, &self.key, &self.validation)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?
+            .claims;
+        let id = self
+            .ids
+            .decode(&claims.sub)
+            .ok_or_else(|| AuthError::InvalidToken("subject is not a user".into()))?;
+        Ok(AuthUser {
+            id,
+            claims,
+            roles: vec![],
+        })
+    }
+}
+
+#[async_trait]
+impl Authenticator for TokenAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+        let repo = RdbRepository::new(&self.conn);
+        if !ApiKeySecret::is_api_key(token) {
+            let user = if SessionToken::is_session_token(token) {
+                self.verify_session(&repo, token).await?
+            } else {
+                self.verify_jwt(token)?
+            };
+            let roles = match auth::get_roles(&repo, &user.id).await {
+                Ok(x) => x,
+                Err(DomainError::InvalidCredentials) => {
+                    return Err(AuthError::InvalidToken("user is unknown or deleted".into()))
+                }
+                Err(e) => return Err(AuthError::Failed(e)),
+            };
+            return Ok(Principal::User(AuthUser { roles, ..user }));
+        }
+        match api_keys::authenticate_api_key(&repo, &ApiKeySecret(token.into())).await {
+            Ok(key) => Ok(Principal::ApiKey(key)),
+            Err(DomainError::InvalidCredentials) => Err(AuthError::InvalidToken(
+                "API key is unknown, expired or revoked".into(),
+            )),
+            Err(e) => Err(AuthError::Failed(e)),
+        }
+    }
+}
+
+/// `bytes` without trailing ASCII whitespace.
+fn trim_end(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|x| !x.is_ascii_whitespace())
+        .map_or(0, |x| x + 1);
+    &bytes[..end]
+}
+
+/// Why a request was not let through. Failed authentication is answered with `401` and
+/// insufficient permissions with `403`, along with a `WWW-Authenticate` challenge as in RFC 6750.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("bearer token is required")]
+    Missing,
+    #[error("bearer token is invalid: {0}")]
+    InvalidToken(String),
+    #[error("{0} scope is required")]
+    InsufficientScope(Scope),
+    #[error("only users can do this, not API keys")]
+    NotAUser,
+    /// The token could not be checked at all.
+    #[error(transparent)]
+    Failed(DomainError),
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, challenge) = match self {
+            AuthError::Failed(e) => return e.into_response(),
+            AuthError::Missing => (StatusCode::UNAUTHORIZED, Some("Bearer".into())),
+            AuthError::InvalidToken(_) => (
+                StatusCode::UNAUTHORIZED,
+                Some(r#"Bearer error="invalid_token""#.into()),
+            ),
+            AuthError::InsufficientScope(scope) => (
+                StatusCode::FORBIDDEN,
+                Some(format!(
+                    r#"Bearer error="insufficient_scope", scope="{}""#,
+                    scope
+                )),
+            ),
+            AuthError::NotAUser => (StatusCode::FORBIDDEN, None),
+        };
+        let problem = Problem::new(status).with_detail(self.to_string());
+        match challenge {
+            Some(x) => ([(header::WWW_AUTHENTICATE, x)], problem).into_response(),
+            None => problem.into_response(),
+        }
+    }
+}
+
+/// Middleware that lets only requests with a valid bearer token through, making their
+/// [`Principal`] available to handlers.
+pub async fn authenticate<B>(
+    State(authenticator): State<Arc<dyn Authenticator>>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(bearer_token);
+    let principal = match token {
+        Some(x) => match authenticator.authenticate(x).await {
+            Ok(principal) => principal,
+            Err(e) => return e.into_response(),
+        },
+        None => return AuthError::Missing.into_response(),
+    };
+    req.extensions_mut().insert(principal);
+    next.run(req).await
+}
+
+/// The token of an `Authorization` header value with the `Bearer` scheme, whose name is
+/// case-insensitive as in RFC 7235.
+fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("Bearer").then(|| token.trim())
+}
+
+/// Middleware for the routes under `/users`, which need the `users:read` scope to read and
+/// `users:write` to change anything. It has to run after [`authenticate`].
+pub async fn authorize_users<B>(principal: Principal, req: Request<B>, next: Next<B>) -> Response {
+    let scope = if req.method().is_safe() {
+        Scope::UsersRead
+    } else {
+        Scope::UsersWrite
+    };
+    if !principal.has_scope(scope) {
+        return AuthError::InsufficientScope(scope).into_response();
+    }
+    next.run(req).await
+}
+
+/// Only available on routes behind [`authenticate`]; elsewhere the request is unauthorized.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or(AuthError::Missing)
+    }
+}
+
+/// Whoever the request was authenticated as, for usecases to authorize.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Subject {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Principal::from_request_parts(parts, state).await?.subject())
+    }
+}
+
+/// Like [`Principal`], and forbidden to API keys.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Principal::from_request_parts(parts, state).await? {
+            Principal::User(user) => Ok(user),
+            Principal::ApiKey(_) => Err(AuthError::NotAUser),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::{fs, sync::Arc};
+
+    use async_trait::async_trait;
+    use chrono::{Duration, Utc};
+    use jsonwebtoken::{EncodingKey, Header};
+
+    use crate::{
+        config::CONFIG,
+        domain::{
+            api_key::{ApiKey, ApiKeyId, ApiKeySecret, Scope},
+            user::UserId,
+        },
+        interface::public_id::PublicIds,
+    };
+
+    use super::{AuthError, Authenticator, Claims, Principal};
+
+    /// Secret of a key that may read and write users, which [`ClientAuthenticator`] accepts
+    /// without it being stored.
+    pub const CLIENT_KEY: &str = "exk_api-tests-read-and-write-users";
+
+    /// Accepts [`CLIENT_KEY`] and leaves any other token to the authenticator it wraps, so that
+    /// tests can send requests as a client without a key in the database.
+    #[derive(Debug)]
+    pub struct ClientAuthenticator(pub Arc<dyn Authenticator>);
+
+    #[async_trait]
+    impl Authenticator for ClientAuthenticator {
+        async fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+            if token != CLIENT_KEY {
+                return self.0.authenticate(token).await;
+            }
+            Ok(Principal::ApiKey(ApiKey {
+                id: ApiKeyId(0),
+                prefix: ApiKeySecret(CLIENT_KEY.into()).prefix().into(),
+                owner: "api tests".into(),
+                scopes: vec![Scope::UsersRead, Scope::UsersWrite],
+                expires_at: None,
+                last_used_at: None,
+                revoked_at: None,
+                created_at: Utc::now(),
+            }))
+        }
+    }
+
+    /// Claims for the user that expire in an hour.
+    pub fn claims(id: &UserId) -> Claims {
+        Claims {
+            sub: PublicIds::new(&CONFIG.public_id_secret).encode(id),
+            exp: (Utc::now() + Duration::hours(1)).timestamp() as u64,
+            iat: None,
+        }
+    }
+
+    /// A token signed with the test HS256 key.
+    pub fn token(claims: &Claims) -> String {
+        let secret = fs::read(&CONFIG.jwt_key_file).unwrap();
+        jsonwebtoken::encode(
+            &Header::default(),
+            claims,
+            &EncodingKey::from_secret(super::trim_end(&secret)),
+        )
+        .unwrap()
+    }
+
+    /// `Authorization` header value for the user.
+    pub fn bearer(id: &UserId) -> String {
+        format!("Bearer {}", token(&claims(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use jsonwebtoken::{EncodingKey, Header};
+    use pretty_assertions::assert_eq;
+
+    use crate::{config::CONFIG, domain::api_key::ApiKeyId};
+
+    use super::{fixtures::*, *};
+
+    const TESTDATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/jwt");
+
+    fn ids() -> PublicIds {
+        PublicIds::new(&CONFIG.public_id_secret)
+    }
+
+    fn authenticator(algorithm: Algorithm, key_file: &str) -> anyhow::Result<TokenAuthenticator> {
+        let mut config = CONFIG.clone();
+        config.jwt_algorithm = algorithm;
+        config.jwt_key_file = format!("{}/{}", TESTDATA, key_file).into();
+        config.jwt_issuer = Some("https://issuer.example.com".into());
+        TokenAuthenticator::from_config(&config, ids(), DatabaseConnection::Disconnected)
+    }
+
+    #[derive(Serialize)]
+    struct IssuedClaims {
+        #[serde(flatten)]
+        claims: Claims,
+        iss: &'static str,
+    }
+
+    fn issued(id: &UserId) -> IssuedClaims {
+        IssuedClaims {
+            claims: claims(id),
+            iss: "https://issuer.example.com",
+        }
+    }
+
+    #[test]
+    fn test_authenticate() -> anyhow::Result<()> {
+        let claims = claims(&UserId(100));
+        let user =
+            TokenAuthenticator::from_config(&CONFIG, ids(), DatabaseConnection::Disconnected)?
+                .verify_jwt(&token(&claims))
+                .unwrap();
+
+        assert_eq!(
+            user,
+            AuthUser {
+                id: UserId(100),
+                claims,
+                roles: vec![],
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_authenticate_rs256() -> anyhow::Result<()> {
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::RS256),
+            &issued(&UserId(100)),
+            &EncodingKey::from_rsa_pem(&fs::read(format!("{}/rs256.pem", TESTDATA))?)?,
+        )?;
+
+        let user = authenticator(Algorithm::RS256, "rs256.pub.pem")?
+            .verify_jwt(&token)
+            .unwrap();
+
+        assert_eq!(user.id, UserId(100));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_authenticate_invalid() -> anyhow::Result<()> {
+        let hs256 = authenticator(Algorithm::HS256, "hs256.key")?;
+        let sign = |claims: &IssuedClaims, key: &[u8]| {
+            jsonwebtoken::encode(&Header::default(), claims, &EncodingKey::from_secret(key))
+        };
+        let secret = fs::read(format!("{}/hs256.key", TESTDATA))?;
+        let secret = trim_end(&secret);
+
+        let expired = IssuedClaims {
+            claims: Claims {
+                exp: (Utc::now().timestamp() - 3600) as u64,
+                ..claims(&UserId(100))
+            },
+            ..issued(&UserId(100))
+        };
+        let other_issuer = IssuedClaims {
+            iss: "https://other.example.com",
+            ..issued(&UserId(100))
+        };
+        let not_a_user = IssuedClaims {
+            claims: Claims {
+                sub: "alice".into(),
+                ..claims(&UserId(100))
+            },
+            ..issued(&UserId(100))
+        };
+        // Signed with the public key as an HS256 secret, an old trick against RS256 verifiers.
+        let public_key = fs::read(format!("{}/rs256.pub.pem", TESTDATA))?;
+        for token in [
+            sign(&expired, secret)?,
+            sign(&other_issuer, secret)?,
+            sign(&not_a_user, secret)?,
+            sign(&issued(&UserId(100)), b"other secret")?,
+            "not a token".into(),
+        ] {
+            assert_matches!(hs256.verify_jwt(&token), Err(AuthError::InvalidToken(_)));
+        }
+        assert_matches!(
+            authenticator(Algorithm::RS256, "rs256.pub.pem")?
+                .verify_jwt(&sign(&issued(&UserId(100)), &public_key)?),
+            Err(AuthError::InvalidToken(_))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_algorithm() {
+        assert!(authenticator(Algorithm::ES256, "hs256.key").is_err());
+    }
+
+    #[test]
+    fn test_principal() {
+        let user = Principal::User(AuthUser {
+            id: UserId(100),
+            claims: claims(&UserId(100)),
+            roles: vec![Role::Viewer],
+        });
+        let key = Principal::ApiKey(ApiKey {
+            id: ApiKeyId(1),
+            prefix: "exk_abcdefgh".into(),
+            owner: "batch".into(),
+            scopes: vec![Scope::UsersRead],
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: Utc::now(),
+        });
+
+        assert!(user.has_scope(Scope::UsersWrite));
+        assert!(key.has_scope(Scope::UsersRead));
+        assert!(!key.has_scope(Scope::UsersWrite));
+        assert_eq!(user.actor(), Actor(claims(&UserId(100)).sub));
+        assert_eq!(key.actor(), Actor("api-key:exk_abcdefgh".into()));
+        assert_eq!(
+            user.subject(),
+            Subject::User {
+                id: UserId(100),
+                roles: vec![Role::Viewer]
+            }
+        );
+        assert_eq!(
+            key.subject(),
+            Subject::ApiKey {
+                scopes: vec![Scope::UsersRead]
+            }
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("Bearer abc", Some("abc"))]
+    #[case("bearer abc", Some("abc"))]
+    #[case("BEARER  abc ", Some("abc"))]
+    #[case("Basic abc", None)]
+    #[case("Bearer", None)]
+    #[case("Bearerabc", None)]
+    fn test_bearer_token(#[case] value: &str, #[case] expected: Option<&str>) {
+        assert_eq!(bearer_token(value), expected);
+    }
+
+    #[test]
+    fn test_auth_error_response() {
+        let res = AuthError::InsufficientScope(Scope::UsersWrite).into_response();
+
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="insufficient_scope", scope="users:write""#
+        );
+        assert_eq!(
+            AuthError::Failed(DomainError::Unavailable("down".into()))
+                .into_response()
+                .status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use validator::{ValidationError, ValidationErrors};
+
+use crate::domain::error::{Constraint, DomainError};
+
+use super::problem::Problem;
+
+impl From<DomainError> for Problem {
+    fn from(e: DomainError) -> Self {
+        match e {
+            DomainError::NotFound => Problem::new(StatusCode::NOT_FOUND).with_detail(e.to_string()),
+            DomainError::Validation(errors) => Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .with_type("/problems/validation-error", "Validation failed")
+                .with_detail("request has invalid fields")
+                .with_errors(errors),
+            // Versions only ever come from `If-Match`, so a stale one is a failed precondition.
+            DomainError::VersionConflict => {
+                Problem::new(StatusCode::PRECONDITION_FAILED).with_detail(e.to_string())
+            }
+            DomainError::InvalidCredentials => {
+                Problem::new(StatusCode::UNAUTHORIZED).with_detail(e.to_string())
+            }
+            DomainError::Forbidden => {
+                Problem::new(StatusCode::FORBIDDEN).with_detail(e.to_string())
+            }
+            DomainError::SerializationFailure => {
+                Problem::new(StatusCode::CONFLICT).with_detail(e.to_string())
+            }
+            DomainError::UniqueViolation(ref constraint) => with_field_error(
+                Problem::new(StatusCode::CONFLICT).with_detail(e.to_string()),
+                constraint,
+                "unique",
+            ),
+            DomainError::ForeignKeyViolation(ref constraint) => with_field_error(
+                Problem::new(StatusCode::CONFLICT).with_detail(e.to_string()),
+                constraint,
+                "reference",
+            ),
+            // Reported like the validation the value should not have got past.
+            DomainError::CheckViolation(ref constraint) => with_field_error(
+                Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+                    .with_type("/problems/validation-error", "Validation failed")
+                    .with_detail(e.to_string()),
+                constraint,
+                "check",
+            ),
+            // What failed is for the logs, not for clients.
+            DomainError::Unavailable(_) => {
+                tracing::warn!("{}", e);
+                Problem::new(StatusCode::SERVICE_UNAVAILABLE)
+                    .with_detail("temporarily unavailable, try again later")
+            }
+            DomainError::Internal(e) => {
+                tracing::error!("{:?}", e);
+                Problem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
+/// Names the field `constraint` guards in `errors`, in the shape validation failures have.
+fn with_field_error(problem: Problem, constraint: &Constraint, code: &'static str) -> Problem {
+    match constraint.field {
+        Some(field) => {
+            let mut errors = ValidationErrors::new();
+            errors.add(field, ValidationError::new(code));
+            problem.with_errors(errors)
+        }
+        None => problem,
+    }
+}
+
+impl IntoResponse for DomainError {
+    fn into_response(self) -> Response {
+        Problem::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    fn constraint(field: Option<&'static str>) -> Constraint {
+        Constraint {
+            name: "users_email_key".into(),
+            field,
+        }
+    }
+
+    #[rstest]
+    #[case(DomainError::NotFound, StatusCode::NOT_FOUND)]
+    #[case(
+        DomainError::Validation(ValidationErrors::new()),
+        StatusCode::UNPROCESSABLE_ENTITY
+    )]
+    #[case(DomainError::VersionConflict, StatusCode::PRECONDITION_FAILED)]
+    #[case(DomainError::UniqueViolation(constraint(None)), StatusCode::CONFLICT)]
+    #[case(
+        DomainError::ForeignKeyViolation(constraint(None)),
+        StatusCode::CONFLICT
+    )]
+    #[case(
+        DomainError::CheckViolation(constraint(None)),
+        StatusCode::UNPROCESSABLE_ENTITY
+    )]
+    #[case(DomainError::InvalidCredentials, StatusCode::UNAUTHORIZED)]
+    #[case(DomainError::Forbidden, StatusCode::FORBIDDEN)]
+    #[case(DomainError::SerializationFailure, StatusCode::CONFLICT)]
+    #[case(DomainError::Unavailable("".into()), StatusCode::SERVICE_UNAVAILABLE)]
+    #[case(DomainError::Internal(anyhow::anyhow!("")), StatusCode::INTERNAL_SERVER_ERROR)]
+    fn test_status(#[case] err: DomainError, #[case] status: StatusCode) {
+        assert_eq!(err.into_response().status(), status);
+    }
+
+    #[tokio::test]
+    async fn test_validation_body() -> anyhow::Result<()> {
+        let mut errors = ValidationErrors::new();
+        errors.add("name", ValidationError::new("length"));
+
+        let res = DomainError::Validation(errors).into_response();
+        let body: serde_json::Value =
+            serde_json::from_slice(&hyper::body::to_bytes(res.into_body()).await?)?;
+
+        assert_eq!(body["type"], json!("/problems/validation-error"));
+        assert_eq!(body["status"], json!(422));
+        assert_eq!(body["errors"]["name"][0]["code"], json!("length"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(Some("email"), json!({"email": [{"code": "unique", "message": null, "params": {}}]}))]
+    #[case(None, json!(null))]
+    #[tokio::test]
+    async fn test_unique_violation_body(
+        #[case] field: Option<&'static str>,
+        #[case] errors: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let res = DomainError::UniqueViolation(constraint(field)).into_response();
+        let body: serde_json::Value =
+            serde_json::from_slice(&hyper::body::to_bytes(res.into_body()).await?)?;
+
+        assert_eq!(body["status"], json!(409));
+        assert_eq!(
+            body["detail"],
+            json!(format!(
+                "{} is already taken",
+                field.unwrap_or("users_email_key")
+            ))
+        );
+        assert_eq!(body["errors"], errors);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_internal_body_hides_detail() -> anyhow::Result<()> {
+        let res = DomainError::Internal(anyhow::anyhow!("secret")).into_response();
+        let body: serde_json::Value =
+            serde_json::from_slice(&hyper::body::to_bytes(res.into_body()).await?)?;
+
+        assert_eq!(body.get("detail"), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_body_hides_cause() -> anyhow::Result<()> {
+        let res = DomainError::Unavailable("connection to 10.0.0.1 refused".into()).into_response();
+        let body: serde_json::Value =
+            serde_json::from_slice(&hyper::body::to_bytes(res.into_body()).await?)?;
+
+        assert_eq!(body["status"], json!(503));
+        assert_eq!(
+            body["detail"],
+            json!("temporarily unavailable, try again later")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! Extractors that reject with [`Problem`] instead of axum's plain-text rejections.
+
+use std::{error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    body::HttpBody,
+    extract::multipart::{MultipartError, MultipartRejection},
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    extract::{FromRef, FromRequest, FromRequestParts},
+    http::{header, request::Parts, Request, StatusCode},
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use chrono::NaiveDate;
+use http_body::LengthLimitError;
+use serde::{de::DeserializeOwned, Serialize};
+use validator::ValidateArgs;
+
+use crate::{
+    domain::{
+        audit::Actor,
+        clock::Clock,
+        error::DomainError,
+        user::{UserId, Version},
+    },
+    interface::public_id::PublicIds,
+};
+
+use super::{auth::Principal, problem::Problem};
+
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(Problem))]
+pub struct Path<T>(pub T);
+
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(Problem))]
+pub struct Query<T>(pub T);
+
+#[derive(Debug, FromRequest)]
+#[from_request(via(axum::Json), rejection(Problem))]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// User named by the `:id` path parameter, which holds its public id. A parameter that is not a
+/// public id names no user.
+#[derive(Debug)]
+pub struct UserPath(pub UserId);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for UserPath
+where
+    PublicIds: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(id) = Path::<String>::from_request_parts(parts, state).await?;
+        PublicIds::from_ref(state)
+            .decode(&id)
+            .map(Self)
+            .ok_or_else(|| DomainError::NotFound.into())
+    }
+}
+
+/// JSON body that has also passed its validation rules, as of the day the state's [`Clock`] says
+/// it is.
+#[derive(Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for ValidatedJson<T>
+where
+    T: DeserializeOwned + for<'v> ValidateArgs<'v, Args = &'v NaiveDate>,
+    Arc<dyn Clock>: FromRef<S>,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Problem;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let today = Arc::<dyn Clock>::from_ref(state).today();
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        value.validate_args(&today).map_err(DomainError::from)?;
+        Ok(Self(value))
+    }
+}
+
+/// `multipart/form-data` body.
+pub struct Multipart(pub axum::extract::Multipart);
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for Multipart
+where
+    S: Send + Sync,
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Problem;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(
+            axum::extract::Multipart::from_request(req, state).await?,
+        ))
+    }
+}
+
+/// Version named by the `If-Match` header, which mutations require.
+///
+/// Only a single strong entity tag as produced by [`etag`] can match; anything else fails the
+/// precondition.
+#[derive(Debug)]
+pub struct IfMatch(pub Version);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let value = parts.headers.get(header::IF_MATCH).ok_or_else(|| {
+            Problem::new(StatusCode::PRECONDITION_REQUIRED)
+                .with_detail("If-Match header is required")
+        })?;
+        value
+            .to_str()
+            .ok()
+            .and_then(|x| x.trim().strip_prefix('"')?.strip_suffix('"')?.parse().ok())
+            .map(|x| Self(Version(x)))
+            .ok_or_else(|| DomainError::VersionConflict.into())
+    }
+}
+
+/// Actor for the audit log: whoever the request was authenticated as, or `anonymous` on public
+/// routes.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Actor {
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(match parts.extensions.get::<Principal>() {
+            Some(principal) => principal.actor(),
+            None => Actor("anonymous".into()),
+        })
+    }
+}
+
+/// Strong entity tag for a version, the value of `ETag` and `If-Match`.
+pub fn etag(version: Version) -> String {
+    format!("\"{}\"", version.0)
+}
+
+macro_rules! impl_from_rejection {
+    ($($rejection:ty),*) => {
+        $(
+            impl From<$rejection> for Problem {
+                fn from(rejection: $rejection) -> Self {
+                    Problem::new(rejection.status()).with_detail(rejection.body_text())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_rejection!(
+    JsonRejection,
+    MultipartRejection,
+    PathRejection,
+    QueryRejection
+);
+
+impl From<MultipartError> for Problem {
+    fn from(e: MultipartError) -> Self {
+        let status = if exceeds_body_limit(&e) {
+            StatusCode::PAYLOAD_TOO_LARGE
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        Problem::new(status).with_detail(e.to_string())
+    }
+}
+
+/// Whether reading the body failed because of the route's [`DefaultBodyLimit`]. axum reports it
+/// as any other multipart error, and the parser it wraps hides the cause from `source()`.
+///
+/// [`DefaultBodyLimit`]: axum::extract::DefaultBodyLimit
+fn exceeds_body_limit(e: &MultipartError) -> bool {
+    let mut cause = e.source();
+    while let Some(e) = cause {
+        if e.is::<LengthLimitError>() {
+            return true;
+        }
+        cause = match e.downcast_ref() {
+            Some(multer::Error::StreamReadFailed(e)) => Some(e.as_ref()),
+            _ => e.source(),
+        };
+    }
+    false
+}
@@ -0,0 +1,154 @@
+//! [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) problem details for HTTP APIs.
+
+use axum::{
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use validator::ValidationErrors;
+
+use crate::domain::user::import::ImportError;
+
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension member carrying per-field validation failures.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<ValidationErrors>,
+    /// Extension member carrying the invalid lines of an import.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Vec<ImportError>>,
+}
+
+impl Problem {
+    /// A problem without a specific type, titled by the status' reason phrase.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            type_: "about:blank".into(),
+            title: status.canonical_reason().unwrap_or_default().into(),
+            status: status.as_u16(),
+            detail: None,
+            instance: None,
+            errors: None,
+            lines: None,
+        }
+    }
+
+    pub fn with_type(self, type_: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            type_: type_.into(),
+            title: title.into(),
+            ..self
+        }
+    }
+
+    pub fn with_detail(self, detail: impl Into<String>) -> Self {
+        Self {
+            detail: Some(detail.into()),
+            ..self
+        }
+    }
+
+    pub fn with_errors(self, errors: ValidationErrors) -> Self {
+        Self {
+            errors: Some(errors),
+            ..self
+        }
+    }
+
+    pub fn with_lines(self, lines: Vec<ImportError>) -> Self {
+        Self {
+            lines: Some(lines),
+            ..self
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let mut res = (self.status(), Json(&self)).into_response();
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+        res.extensions_mut().insert(self);
+        res
+    }
+}
+
+/// Middleware that renders every error response as a problem.
+///
+/// Problems raised by handlers get `instance` filled with the request path, and
+/// any other 4xx/5xx response (unmatched routes, disallowed methods, ...) is
+/// replaced by a problem for its status. Headers such as `Allow` are kept.
+pub async fn render<B>(req: Request<B>, next: Next<B>) -> Response {
+    let instance = req.uri().path().to_owned();
+    let res = next.run(req).await;
+
+    let problem = match res.extensions().get::<Problem>() {
+        Some(problem) if problem.instance.is_some() => return res,
+        Some(problem) => problem.clone(),
+        None if res.status().is_client_error() || res.status().is_server_error() => {
+            Problem::new(res.status())
+        }
+        None => return res,
+    };
+
+    let problem = Problem {
+        instance: Some(instance),
+        ..problem
+    };
+    let (mut parts, _) = res.into_parts();
+    parts.extensions.insert(problem.clone());
+    let (rendered, body) = problem.into_response().into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.extend(rendered.headers);
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_json_diff::assert_json_eq;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize() -> anyhow::Result<()> {
+        let problem = Problem::new(StatusCode::NOT_FOUND).with_detail("not found");
+
+        assert_json_eq!(
+            serde_json::to_value(problem)?,
+            json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "not found",
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_into_response() {
+        let res = Problem::new(StatusCode::CONFLICT).into_response();
+
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+    }
+}